@@ -0,0 +1,46 @@
+//! Compares `DaryPriorityQueue` for several arities on insert-heavy and pop-heavy workloads.
+//!
+//! Run with `cargo bench --bench arity`.
+
+mod common;
+
+use std::hint::black_box;
+
+use priority_queue::DaryPriorityQueue;
+
+const N: usize = 1_000_000;
+const ROUNDS: u32 = 10;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let best = common::best_of(ROUNDS, || (), |_| f());
+    println!(
+        "{name:<24} {:>10.2} ns/element",
+        best.as_nanos() as f64 / N as f64
+    );
+}
+
+fn bench_arity<const D: usize>(data: &[u64]) {
+    bench(&format!("insert, D = {D}"), || {
+        let mut queue = DaryPriorityQueue::<_, _, D>::new(Vec::with_capacity(N));
+        for &x in data {
+            queue.insert(x);
+        }
+        black_box(queue);
+    });
+
+    let full = DaryPriorityQueue::<_, _, D>::new(data.to_vec());
+    bench(&format!("take_front, D = {D}"), || {
+        let mut queue = full.clone();
+        while let Some(x) = queue.take_front() {
+            black_box(x);
+        }
+    });
+}
+
+fn main() {
+    let data = common::random_data(N);
+    bench_arity::<2>(&data);
+    bench_arity::<4>(&data);
+    bench_arity::<8>(&data);
+    bench_arity::<16>(&data);
+}
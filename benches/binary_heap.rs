@@ -0,0 +1,67 @@
+//! Compares `PriorityQueue` against `std::collections::BinaryHeap`.
+//!
+//! Run with `cargo bench --bench binary_heap`.
+
+mod common;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::hint::black_box;
+
+use priority_queue::PriorityQueue;
+
+const N: usize = 100_000;
+const ROUNDS: u32 = 20;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let best = common::best_of(ROUNDS, || (), |_| f());
+    println!(
+        "{name:<40} {:>10.2} ns/element",
+        best.as_nanos() as f64 / N as f64
+    );
+}
+
+fn main() {
+    let data = common::random_data(N);
+
+    bench("BinaryHeap<Reverse<u64>>", || {
+        let mut heap = BinaryHeap::new();
+        for &x in &data {
+            heap.push(Reverse(x));
+        }
+        while let Some(x) = heap.pop() {
+            black_box(x);
+        }
+    });
+
+    bench("PriorityQueue<u64, MinOrder>", || {
+        let mut queue = PriorityQueue::new(Vec::new());
+        for &x in &data {
+            queue.insert(x);
+        }
+        while let Some(x) = queue.take_front() {
+            black_box(x);
+        }
+    });
+
+    bench("PriorityQueue<u64, closure>", || {
+        let mut queue = PriorityQueue::with_ordering(Vec::new(), |a: &u64, b: &u64| a < b);
+        for &x in &data {
+            queue.insert(x);
+        }
+        while let Some(x) = queue.take_front() {
+            black_box(x);
+        }
+    });
+
+    bench("PriorityQueue<u64, fn pointer>", || {
+        let less: fn(&u64, &u64) -> bool = |a, b| a < b;
+        let mut queue = PriorityQueue::with_ordering(Vec::new(), black_box(less));
+        for &x in &data {
+            queue.insert(x);
+        }
+        while let Some(x) = queue.take_front() {
+            black_box(x);
+        }
+    });
+}
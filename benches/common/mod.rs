@@ -0,0 +1,34 @@
+//! Helpers shared by the benchmarks, which include this file with `mod common;`.
+
+use std::time::{Duration, Instant};
+
+/// Returns `n` numbers from xorshift64, the same ones on every run, so the benchmarks do not need
+/// a `rand` dependency.
+pub fn random_data(n: usize) -> Vec<u64> {
+    let mut x = 0x2545_f491_4f6c_dd1d_u64;
+    (0..n)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+        .collect()
+}
+
+/// Times `run` on the result of `setup`, once to warm up and then `rounds` more times, and
+/// returns the fastest time. `setup` and dropping its result are not timed.
+pub fn best_of<S>(rounds: u32, mut setup: impl FnMut() -> S, mut run: impl FnMut(&S)) -> Duration {
+    let mut best = Duration::MAX;
+    for round in 0..=rounds {
+        let input = setup();
+        let start = Instant::now();
+        run(&input);
+        let elapsed = start.elapsed();
+        if round > 0 {
+            best = best.min(elapsed);
+        }
+        drop(input);
+    }
+    best
+}
@@ -0,0 +1,98 @@
+//! Compares `ConcurrentPriorityQueue` with a `PriorityQueue` behind a `Mutex` and with
+//! `SyncPriorityQueue`, on a workload where every thread alternates inserts and takes.
+//!
+//! Run with `cargo bench --bench concurrent`.
+
+mod common;
+
+use std::hint::black_box;
+use std::sync::Mutex;
+use std::thread;
+
+use priority_queue::{ConcurrentPriorityQueue, PriorityQueue, SyncPriorityQueue};
+
+/// Operations per round, split evenly between the threads.
+const OPS: usize = 1_000_000;
+/// Elements in the queue before a round starts, so that takes rarely find it empty.
+const PREFILL: usize = 10_000;
+const ROUNDS: u32 = 5;
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+/// Runs `op(queue, i)` for every `i` in `0..OPS`, with each of `threads` threads taking a
+/// contiguous share, after `setup` builds the queue, and prints the best throughput.
+fn bench<Q: Sync>(
+    name: &str,
+    threads: usize,
+    setup: impl Fn() -> Q,
+    op: impl Fn(&Q, usize) + Sync,
+) {
+    let best = common::best_of(ROUNDS, setup, |queue| {
+        thread::scope(|s| {
+            for t in 0..threads {
+                let op = &op;
+                s.spawn(move || {
+                    for i in t * OPS / threads..(t + 1) * OPS / threads {
+                        op(queue, i);
+                    }
+                });
+            }
+        });
+    });
+    println!(
+        "{name:<12} {threads} threads {:>10.2} Mops/s",
+        OPS as f64 / best.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    let data = common::random_data(PREFILL + OPS);
+    let (prefill, data) = data.split_at(PREFILL);
+
+    for threads in THREADS {
+        bench(
+            "concurrent",
+            threads,
+            || {
+                let queue = ConcurrentPriorityQueue::new();
+                for &x in prefill {
+                    queue.insert(x, x);
+                }
+                queue
+            },
+            |queue, i| {
+                if i % 2 == 0 {
+                    queue.insert(data[i], data[i]);
+                } else {
+                    black_box(queue.take_front());
+                }
+            },
+        );
+
+        bench(
+            "mutex",
+            threads,
+            || Mutex::new(PriorityQueue::new(prefill.to_vec())),
+            |queue, i| {
+                let mut queue = queue.lock().unwrap();
+                if i % 2 == 0 {
+                    queue.insert(data[i]);
+                } else {
+                    black_box(queue.take_front());
+                }
+            },
+        );
+
+        bench(
+            "sync",
+            threads,
+            || SyncPriorityQueue::new(prefill.to_vec()),
+            |queue, i| {
+                if i % 2 == 0 {
+                    queue.push(data[i]).unwrap();
+                } else {
+                    black_box(queue.try_pop().ok());
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,495 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use crate::comparator::ByPriority;
+use crate::sift;
+use crate::{Comparator, MinOrder};
+
+/// Refers to an element of an [`AddressablePriorityQueue`].
+///
+/// Returned by [`AddressablePriorityQueue::insert`]. A handle stays valid until its element is
+/// removed from the queue; after that, every method given the handle returns `None` (or `false`),
+/// even if the queue has reused its storage for a newer element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    slot: usize,
+    generation: u64,
+}
+
+struct Slot {
+    /// Index of the element in `heap`, or `None` if the slot is free.
+    position: Option<usize>,
+    generation: u64,
+}
+
+/// A priority queue whose elements can be reached, changed and removed after they are inserted.
+///
+/// Every element keeps a [`Handle`], and the queue keeps track of where each handle's element is
+/// in the heap as elements are moved, so that [`update`], [`remove`] and friends run in
+/// O(log n).
+///
+/// A panicking comparator [poisons] the queue the same way it does a [`GenericPriorityQueue`].
+/// The handles stay valid, and the heap is rebuilt on next use.
+///
+/// [`update`]: AddressablePriorityQueue::update
+/// [`remove`]: AddressablePriorityQueue::remove
+/// [poisons]: AddressablePriorityQueue::is_poisoned
+/// [`GenericPriorityQueue`]: crate::GenericPriorityQueue
+pub struct AddressablePriorityQueue<T, F = MinOrder> {
+    /// Each element is stored with the index of its slot.
+    heap: Vec<(usize, T)>,
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+    cmp: ByPriority<F>,
+    /// Set while the comparator is running, so that it stays set if the comparator panics.
+    poisoned: bool,
+}
+
+impl<T: PartialOrd> AddressablePriorityQueue<T> {
+    pub fn new() -> Self {
+        Self::with_comparator(MinOrder)
+    }
+}
+
+impl<T: PartialOrd> Default for AddressablePriorityQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for AddressablePriorityQueue<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.heap.iter().map(|(_, x)| x))
+            .finish()
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> AddressablePriorityQueue<T, F> {
+    pub fn with_ordering(ordering: F) -> Self {
+        Self::with_comparator(ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> AddressablePriorityQueue<T, F> {
+    pub fn with_comparator(cmp: F) -> Self {
+        Self {
+            heap: Vec::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            cmp: ByPriority(cmp),
+            poisoned: false,
+        }
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        self.repair();
+        Some(self.remove_at(0))
+    }
+
+    pub fn insert(&mut self, element: T) -> Handle {
+        self.repair();
+        let position = self.heap.len();
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot].position = Some(position);
+                slot
+            }
+            None => {
+                self.slots.push(Slot {
+                    position: Some(position),
+                    generation: 0,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.heap.push((slot, element));
+        self.sift_up(position);
+        Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.front().map(|(_, x)| x)
+    }
+
+    /// Returns the handle of the front element.
+    pub fn peek_handle(&self) -> Option<Handle> {
+        self.front().map(|&(slot, _)| Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        })
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.position(handle).map(|i| &self.heap[i].1)
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.position(handle).is_some()
+    }
+
+    /// Modifies the element behind `handle` and moves it to its new place in the queue.
+    ///
+    /// Returns `false` if the handle's element is no longer in the queue.
+    pub fn update(&mut self, handle: Handle, f: impl FnOnce(&mut T)) -> bool {
+        self.repair();
+        let Some(i) = self.position(handle) else {
+            return false;
+        };
+        // The sifts clear the poison once the element is back in place.
+        self.poisoned = true;
+        f(&mut self.heap[i].1);
+        let i = self.sift_up(i);
+        self.sift_down(i);
+        true
+    }
+
+    /// Replaces the element behind `handle` with one that comes out of the queue no later than it,
+    /// and returns the old element.
+    ///
+    /// Only sifts the element up. It is a logic error for `element` to come after the element it
+    /// replaces; use [`update`] when the direction is not known.
+    ///
+    /// [`update`]: AddressablePriorityQueue::update
+    pub fn decrease_key(&mut self, handle: Handle, element: T) -> Option<T> {
+        self.repair();
+        let i = self.position(handle)?;
+        debug_assert!(
+            !self.cmp(&self.heap[i].1, &element),
+            "decrease_key moved an element later in the queue"
+        );
+        let old = core::mem::replace(&mut self.heap[i].1, element);
+        self.sift_up(i);
+        Some(old)
+    }
+
+    /// Replaces the element behind `handle` with one that comes out of the queue no earlier than
+    /// it, and returns the old element.
+    ///
+    /// Only sifts the element down. It is a logic error for `element` to come before the element
+    /// it replaces; use [`update`] when the direction is not known.
+    ///
+    /// [`update`]: AddressablePriorityQueue::update
+    pub fn increase_key(&mut self, handle: Handle, element: T) -> Option<T> {
+        self.repair();
+        let i = self.position(handle)?;
+        debug_assert!(
+            !self.cmp(&element, &self.heap[i].1),
+            "increase_key moved an element earlier in the queue"
+        );
+        let old = core::mem::replace(&mut self.heap[i].1, element);
+        self.sift_down(i);
+        Some(old)
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.repair();
+        let i = self.position(handle)?;
+        Some(self.remove_at(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        for (slot, _) in self.heap.drain(..) {
+            self.slots[slot].position = None;
+            self.slots[slot].generation += 1;
+            self.free_slots.push(slot);
+        }
+        self.poisoned = false;
+    }
+
+    /// Returns `true` if the comparator panicked during an earlier operation, which may have left
+    /// the elements out of heap order.
+    ///
+    /// A poisoned queue still behaves correctly, rebuilding the heap when it is next modified.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    fn cmp(&self, a: &T, b: &T) -> bool {
+        self.cmp.0.before(a, b)
+    }
+
+    /// Returns the front entry, falling back to a linear scan if the queue is poisoned.
+    fn front(&self) -> Option<&(usize, T)> {
+        if self.poisoned {
+            return self
+                .heap
+                .iter()
+                .reduce(|best, x| if self.cmp.before(x, best) { x } else { best });
+        }
+        self.heap.first()
+    }
+
+    fn position(&self, handle: Handle) -> Option<usize> {
+        let slot = self.slots.get(handle.slot)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.position
+    }
+
+    fn remove_at(&mut self, i: usize) -> T {
+        let last = self.heap.len() - 1;
+        self.swap(i, last);
+        let (slot, element) = self.heap.pop().unwrap();
+        self.slots[slot].position = None;
+        self.slots[slot].generation += 1;
+        self.free_slots.push(slot);
+        if i < self.heap.len() {
+            let i = self.sift_up(i);
+            self.sift_down(i);
+        }
+        element
+    }
+
+    /// Swaps two elements of the heap, keeping their slots pointing at them.
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+        self.slots[self.heap[i].0].position = Some(i);
+        self.slots[self.heap[j].0].position = Some(j);
+    }
+
+    fn repair(&mut self) {
+        if self.poisoned {
+            // Stays poisoned if the comparator panics again.
+            sift::heapify_tracked::<_, _, 2>(&mut self.heap, &self.cmp, track(&mut self.slots));
+            self.poisoned = false;
+        }
+    }
+
+    /// Sifts the element at `i` up and returns its new index.
+    fn sift_up(&mut self, i: usize) -> usize {
+        self.poisoned = true;
+        let i =
+            sift::sift_up_tracked::<_, _, 2>(&mut self.heap, &self.cmp, i, track(&mut self.slots));
+        self.poisoned = false;
+        i
+    }
+
+    fn sift_down(&mut self, i: usize) {
+        self.poisoned = true;
+        sift::sift_down_tracked::<_, _, 2>(&mut self.heap, &self.cmp, i, track(&mut self.slots));
+        self.poisoned = false;
+    }
+}
+
+/// Returns a callback for the sifts that points each moved element's slot at its new index.
+fn track<T>(slots: &mut [Slot]) -> impl FnMut(&(usize, T), usize) + '_ {
+    move |&(slot, _), i| slots[slot].position = Some(i)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::xorshift;
+    use crate::{AddressablePriorityQueue, PriorityQueue};
+
+    fn check_positions<T, F>(queue: &AddressablePriorityQueue<T, F>) {
+        for (i, &(slot, _)) in queue.heap.iter().enumerate() {
+            assert_eq!(queue.slots[slot].position, Some(i));
+        }
+    }
+
+    #[test]
+    fn handles() {
+        let mut queue = AddressablePriorityQueue::new();
+        let five = queue.insert(5);
+        let three = queue.insert(3);
+        let eight = queue.insert(8);
+        assert_eq!(queue.get(five), Some(&5));
+        assert_eq!(queue.peek_handle(), Some(three));
+
+        assert_eq!(queue.decrease_key(eight, 1), Some(8));
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.increase_key(eight, 9), Some(1));
+        assert!(queue.update(three, |x| *x = 10));
+        assert_eq!(queue.remove(five), Some(5));
+        assert!(!queue.contains(five));
+        assert_eq!(queue.remove(five), None);
+        check_positions(&queue);
+
+        assert_eq!(queue.take_front(), Some(9));
+        assert_eq!(queue.get(eight), None);
+        assert_eq!(queue.take_front(), Some(10));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn stale_handle_after_slot_reuse() {
+        let mut queue = AddressablePriorityQueue::new();
+        let old = queue.insert(1);
+        queue.take_front();
+        let new = queue.insert(2);
+        assert_eq!(queue.get(old), None);
+        assert!(!queue.update(old, |x| *x = 0));
+        assert_eq!(queue.get(new), Some(&2));
+    }
+
+    #[test]
+    fn random_operations() {
+        let mut next = xorshift(0x9e37_79b9);
+        let mut queue = AddressablePriorityQueue::new();
+        let mut live = Vec::new();
+        for _ in 0..2000 {
+            match next() % 4 {
+                0 | 1 => live.push(queue.insert(next() % 1000)),
+                2 if !live.is_empty() => {
+                    let handle = live.swap_remove(next() as usize % live.len());
+                    assert!(queue.remove(handle).is_some());
+                }
+                _ if !live.is_empty() => {
+                    let handle = live[next() as usize % live.len()];
+                    let value = next() % 1000;
+                    assert!(queue.update(handle, |x| *x = value));
+                }
+                _ => {}
+            }
+            check_positions(&queue);
+        }
+        let mut expected: Vec<_> = live.iter().map(|&h| *queue.get(h).unwrap()).collect();
+        expected.sort();
+        let actual: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn comparator_panic() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let comparisons = Cell::new(0);
+        let panic_at = Cell::new(0);
+        let cmp = |a: &u32, b: &u32| {
+            comparisons.set(comparisons.get() + 1);
+            if comparisons.get() == panic_at.get() {
+                panic!("comparator panicked");
+            }
+            a < b
+        };
+        for i in 1.. {
+            panic_at.set(0);
+            let mut queue = AddressablePriorityQueue::with_ordering(&cmp);
+            let handles: Vec<_> = (0..30).map(|x| queue.insert(x * 17 % 31)).collect();
+            comparisons.set(0);
+            panic_at.set(i);
+            let panicked = catch_unwind(AssertUnwindSafe(|| {
+                queue.update(handles[0], |x| *x = 30);
+                queue.remove(handles[20]);
+                queue.update(handles[29], |x| *x = 0);
+            }))
+            .is_err();
+            panic_at.set(0);
+            assert_eq!(queue.is_poisoned(), panicked);
+            check_positions(&queue);
+            if !panicked {
+                break;
+            }
+
+            let mut expected: Vec<_> = handles
+                .iter()
+                .filter_map(|&h| queue.get(h))
+                .copied()
+                .collect();
+            expected.sort();
+            assert_eq!(queue.peek(), expected.first());
+            let handle = queue.peek_handle().unwrap();
+            assert_eq!(queue.get(handle), expected.first());
+            assert!(queue.update(handle, |x| *x += 1));
+            assert!(!queue.is_poisoned());
+            check_positions(&queue);
+            expected[0] += 1;
+            expected.sort();
+            let actual: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+            assert_eq!(actual, expected, "panic at comparison {i}");
+        }
+    }
+
+    #[test]
+    fn update_with_panicking_closure() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut queue = AddressablePriorityQueue::new();
+        let handles: Vec<_> = (0..10).map(|x| queue.insert(x)).collect();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.update(handles[0], |x| {
+                *x = 20;
+                panic!("closure panicked");
+            })
+        }));
+        assert!(result.is_err());
+        assert!(queue.is_poisoned());
+        assert_eq!(queue.peek(), Some(&1));
+        let actual: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(actual, [1, 2, 3, 4, 5, 6, 7, 8, 9, 20]);
+    }
+
+    #[test]
+    fn dijkstra() {
+        // (from, to, weight)
+        let edges = [
+            (0, 1, 7),
+            (0, 2, 9),
+            (0, 5, 14),
+            (1, 2, 10),
+            (1, 3, 15),
+            (2, 3, 11),
+            (2, 5, 2),
+            (3, 4, 6),
+            (4, 5, 9),
+        ];
+        let n = 6;
+        let mut adjacency = vec![Vec::new(); n];
+        for &(a, b, w) in &edges {
+            adjacency[a].push((b, w));
+            adjacency[b].push((a, w));
+        }
+
+        let mut dist = vec![u32::MAX; n];
+        let mut handles = vec![None; n];
+        let mut queue = AddressablePriorityQueue::with_ordering(|a: &(u32, usize), b| a.0 < b.0);
+        dist[0] = 0;
+        handles[0] = Some(queue.insert((0, 0)));
+        while let Some((d, u)) = queue.take_front() {
+            for &(v, w) in &adjacency[u] {
+                if d + w < dist[v] {
+                    dist[v] = d + w;
+                    match handles[v] {
+                        Some(handle) if queue.contains(handle) => {
+                            queue.decrease_key(handle, (dist[v], v));
+                        }
+                        _ => handles[v] = Some(queue.insert((dist[v], v))),
+                    }
+                }
+            }
+        }
+        assert_eq!(dist, [0, 7, 9, 20, 20, 11]);
+
+        // Sanity check against the lazy-deletion approach.
+        let mut lazy_dist = vec![u32::MAX; n];
+        let mut lazy = PriorityQueue::new(vec![(0, 0)]);
+        while let Some((d, u)) = lazy.take_front() {
+            if d >= lazy_dist[u] {
+                continue;
+            }
+            lazy_dist[u] = d;
+            for &(v, w) in &adjacency[u] {
+                lazy.insert((d + w, v));
+            }
+        }
+        assert_eq!(dist, lazy_dist);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{ArrayStorage, Comparator, GenericPriorityQueue, MinOrder};
+
+/// A binary-heap priority queue that stores up to `N` elements inline, without allocating.
+///
+/// Available without the `alloc` feature. [`try_insert`] hands the element back instead of
+/// growing when the queue is full.
+///
+/// [`try_insert`]: GenericPriorityQueue::try_insert
+pub type ArrayPriorityQueue<T, const N: usize, F = MinOrder> =
+    GenericPriorityQueue<T, ArrayStorage<T, N>, F>;
+
+impl<T: PartialOrd, const N: usize> ArrayPriorityQueue<T, N> {
+    pub fn new() -> Self {
+        Self::with_comparator(MinOrder)
+    }
+}
+
+impl<T: PartialOrd, const N: usize> Default for ArrayPriorityQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize, F: Fn(&T, &T) -> bool> ArrayPriorityQueue<T, N, F> {
+    pub fn with_ordering(ordering: F) -> Self {
+        Self::with_comparator(ordering)
+    }
+}
+
+impl<T, const N: usize, F: Comparator<T>> ArrayPriorityQueue<T, N, F> {
+    pub fn with_comparator(cmp: F) -> Self {
+        Self::with_storage(ArrayStorage::new(), cmp)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ArrayPriorityQueue, HeapStorage, TryInsertError};
+
+    #[test]
+    fn try_insert() {
+        let mut queue = ArrayPriorityQueue::<_, 4>::new();
+        assert_eq!(queue.capacity(), 4);
+        for x in [3, 1, 4, 2] {
+            assert_eq!(queue.try_insert(x), Ok(()));
+        }
+        assert!(queue.is_full());
+        assert_eq!(
+            queue.try_insert(0).map_err(TryInsertError::into_element),
+            Err(0)
+        );
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.try_insert(0), Ok(()));
+        assert_eq!(queue.len(), 4);
+        let order: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(order, [0, 2, 3, 4]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn custom_ordering() {
+        let mut queue = ArrayPriorityQueue::<_, 8, _>::with_ordering(|a: &i32, b: &i32| a > b);
+        for x in [3, 2, 6, 5, 1, 4] {
+            queue.try_insert(x).unwrap();
+        }
+        let order: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(order, [6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn drops_remaining_elements() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        let mut queue =
+            ArrayPriorityQueue::<_, 16, _>::with_ordering(|a: &(i32, Rc<()>), b| a.0 < b.0);
+        for i in 0..10 {
+            queue.try_insert((i, token.clone())).unwrap();
+        }
+        drop(queue.take_front());
+        assert_eq!(Rc::strong_count(&token), 10);
+        drop(queue);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn queue_operations() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut queue = ArrayPriorityQueue::<_, 8>::new();
+        queue.insert(5);
+        queue.extend([3, 9, 1]);
+        *queue.peek_mut().unwrap() = 7;
+        assert_eq!(queue.peek(), Some(&3));
+        queue.retain(|&x| x != 9);
+        assert_eq!(queue.iter().len(), 3);
+
+        let mut other = ArrayPriorityQueue::<_, 8>::new();
+        other.extend([8, 2, 6, 4]);
+        queue.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(queue.drain_sorted().take(2).collect::<Vec<_>>(), [2, 3]);
+        assert!(queue.is_empty());
+
+        queue.extend([4, 1, 3, 2]);
+        assert_eq!(queue.into_sorted_storage().as_slice(), [1, 2, 3, 4]);
+
+        let mut queue = ArrayPriorityQueue::<_, 2>::new();
+        queue.extend([1, 2]);
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.insert(3))).is_err());
+        let mut other = ArrayPriorityQueue::<_, 2>::new();
+        other.insert(0);
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.append(&mut other))).is_err());
+        assert_eq!(other.len(), 1);
+        assert_eq!(queue.into_iter_sorted().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut queue = ArrayPriorityQueue::<i32, 0>::new();
+        assert_eq!(
+            queue.try_insert(1).map_err(TryInsertError::into_element),
+            Err(1)
+        );
+        assert_eq!(queue.take_front(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use crate::{Comparator, MinOrder, PriorityQueue, Reverse};
+
+/// A priority queue that holds at most a fixed number of elements, keeping the ones that come
+/// first in its ordering.
+///
+/// Internally the queue is ordered in reverse, so the element that would be evicted is always at
+/// the root and [`insert`] runs in O(log k) with O(k) memory, however many elements are offered.
+///
+/// [`insert`]: BoundedPriorityQueue::insert
+pub struct BoundedPriorityQueue<T, F = MinOrder> {
+    queue: PriorityQueue<T, Reverse<F>>,
+    limit: usize,
+}
+
+impl<T: PartialOrd> BoundedPriorityQueue<T> {
+    pub fn new(limit: usize) -> Self {
+        Self::with_comparator(limit, MinOrder)
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for BoundedPriorityQueue<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.queue.fmt(f)
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> BoundedPriorityQueue<T, F> {
+    pub fn with_ordering(limit: usize, ordering: F) -> Self {
+        Self::with_comparator(limit, ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> BoundedPriorityQueue<T, F> {
+    pub fn with_comparator(limit: usize, cmp: F) -> Self {
+        Self {
+            queue: PriorityQueue::with_comparator(Vec::new(), Reverse(cmp)),
+            limit,
+        }
+    }
+
+    /// Inserts `element`, keeping only the first `limit` elements in the queue's ordering.
+    ///
+    /// Returns the element that did not fit: `None` if the queue was not full, the evicted back
+    /// element if `element` comes before it, or `element` itself otherwise.
+    pub fn insert(&mut self, element: T) -> Option<T> {
+        if self.queue.len() < self.limit {
+            self.queue.insert(element);
+            return None;
+        }
+        match self.queue.peek() {
+            Some(back) if self.queue.cmp.0.before(&element, back) => {
+                let mut back = self.queue.peek_mut().unwrap();
+                Some(core::mem::replace(&mut *back, element))
+            }
+            _ => Some(element),
+        }
+    }
+
+    /// Returns the element that would be evicted next.
+    pub fn peek_back(&self) -> Option<&T> {
+        self.queue.peek()
+    }
+
+    /// Removes the element that would be evicted next.
+    pub fn take_back(&mut self) -> Option<T> {
+        self.queue.take_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() >= self.limit
+    }
+
+    /// Returns the maximum number of elements the queue holds.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Consumes the queue and returns its elements, front first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut sorted = self.queue.into_sorted_vec();
+        sorted.reverse();
+        sorted
+    }
+}
+
+impl<T, F: Comparator<T>> Extend<T> for BoundedPriorityQueue<T, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+/// Returns the first `k` elements of `iter` in the order given by `ordering`, front first.
+///
+/// Uses O(k) memory however long `iter` is.
+pub fn top_k<T, I, F>(iter: I, k: usize, ordering: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T, &T) -> bool,
+{
+    let mut queue = BoundedPriorityQueue::with_ordering(k, ordering);
+    queue.extend(iter);
+    queue.into_sorted_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{top_k, BoundedPriorityQueue};
+
+    #[test]
+    fn evicts_back_element() {
+        let mut queue = BoundedPriorityQueue::new(3);
+        assert_eq!(queue.insert(5), None);
+        assert_eq!(queue.insert(1), None);
+        assert_eq!(queue.insert(4), None);
+        assert!(queue.is_full());
+        assert_eq!(queue.peek_back(), Some(&5));
+        assert_eq!(queue.insert(2), Some(5));
+        assert_eq!(queue.insert(9), Some(9));
+        assert_eq!(queue.insert(4), Some(4));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.into_sorted_vec(), [1, 2, 4]);
+    }
+
+    #[test]
+    fn zero_limit() {
+        let mut queue = BoundedPriorityQueue::new(0);
+        assert_eq!(queue.insert(1), Some(1));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn top_k_scores() {
+        let scores = (0..10_000u32).map(|i| i.wrapping_mul(2_654_435_761) % 100_000);
+        let mut expected: Vec<_> = scores.clone().collect();
+        expected.sort_by(|a, b| b.cmp(a));
+        expected.truncate(10);
+        assert_eq!(top_k(scores, 10, |a, b| a > b), expected);
+        assert_eq!(top_k([3, 1, 2], 10, |a, b| a < b), [1, 2, 3]);
+    }
+}
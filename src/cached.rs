@@ -0,0 +1,52 @@
+use alloc::vec::Vec;
+
+use crate::{Comparator, PriorityQueue};
+
+/// Orders `(key, element)` entries by their key alone.
+struct CachedKeyOrder;
+
+impl<K: Ord, T> Comparator<(K, T)> for CachedKeyOrder {
+    fn before(&self, a: &(K, T), b: &(K, T)) -> bool {
+        a.0 < b.0
+    }
+}
+
+/// A priority queue ordered by a key that is computed once per element, when it is inserted.
+///
+/// Returned by [`PriorityQueue::by_cached_key`]. Useful when the key is expensive to compute, since
+/// [`PriorityQueue::by_key`] recomputes both keys on every comparison.
+pub struct CachedKeyPriorityQueue<T, K: Ord, G: Fn(&T) -> K> {
+    queue: PriorityQueue<(K, T), CachedKeyOrder>,
+    key: G,
+}
+
+impl<T, K: Ord, G: Fn(&T) -> K> CachedKeyPriorityQueue<T, K, G> {
+    pub(crate) fn new(data: Vec<T>, key: G) -> Self {
+        let entries = data.into_iter().map(|x| (key(&x), x)).collect();
+        Self {
+            queue: PriorityQueue::with_comparator(entries, CachedKeyOrder),
+            key,
+        }
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        self.queue.take_front().map(|(_, x)| x)
+    }
+
+    pub fn insert(&mut self, element: T) {
+        let key = (self.key)(&element);
+        self.queue.insert((key, element));
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.queue.peek().map(|(_, x)| x)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
@@ -0,0 +1,103 @@
+/// Decides which of two elements leaves a priority queue first.
+///
+/// Implemented for every `Fn(&T, &T) -> bool` closure, so anything accepted by
+/// [`PriorityQueue::with_ordering`] is a comparator. The named implementors below are zero-sized
+/// (or as large as the key function they wrap), so comparisons can be inlined.
+///
+/// [`PriorityQueue::with_ordering`]: crate::PriorityQueue::with_ordering
+pub trait Comparator<T> {
+    /// Returns `true` if `a` should be taken from the queue before `b`.
+    fn before(&self, a: &T, b: &T) -> bool;
+}
+
+impl<T, F: Fn(&T, &T) -> bool> Comparator<T> for F {
+    #[inline]
+    fn before(&self, a: &T, b: &T) -> bool {
+        self(a, b)
+    }
+}
+
+/// Takes the smallest element first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinOrder;
+
+impl<T: PartialOrd> Comparator<T> for MinOrder {
+    #[inline]
+    fn before(&self, a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+/// Takes the largest element first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxOrder;
+
+impl<T: PartialOrd> Comparator<T> for MaxOrder {
+    #[inline]
+    fn before(&self, a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+/// Takes the element with the smallest key first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ByKey<G>(pub G);
+
+impl<T, K: Ord, G: Fn(&T) -> K> Comparator<T> for ByKey<G> {
+    #[inline]
+    fn before(&self, a: &T, b: &T) -> bool {
+        (self.0)(a) < (self.0)(b)
+    }
+}
+
+/// Reverses another comparator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reverse<C>(pub C);
+
+impl<T, C: Comparator<T>> Comparator<T> for Reverse<C> {
+    #[inline]
+    fn before(&self, a: &T, b: &T) -> bool {
+        self.0.before(b, a)
+    }
+}
+
+/// Checks that another comparator is a strict ordering on every pair it is asked to compare.
+///
+/// Each comparison also checks that neither element comes before itself (irreflexivity) and that
+/// the two elements do not both come before each other (asymmetry), and panics if either fails.
+/// Every comparison calls the inner comparator three times, or four when `a` comes before `b`, so
+/// it is meant for debugging a comparator that gives a wrong pop order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Validated<C>(pub C);
+
+impl<T, C: Comparator<T>> Comparator<T> for Validated<C> {
+    fn before(&self, a: &T, b: &T) -> bool {
+        assert!(
+            !self.0.before(a, a) && !self.0.before(b, b),
+            "comparator is not irreflexive: an element comes before itself"
+        );
+        let result = self.0.before(a, b);
+        assert!(
+            !(result && self.0.before(b, a)),
+            "comparator is not asymmetric: two elements each come before the other"
+        );
+        result
+    }
+}
+
+/// Orders `(key, priority)` pairs by their priority alone.
+#[cfg(feature = "alloc")]
+pub(crate) struct ByPriority<F>(pub F);
+
+#[cfg(feature = "alloc")]
+impl<K, P, F: Comparator<P>> Comparator<(K, P)> for ByPriority<F> {
+    #[inline]
+    fn before(&self, a: &(K, P), b: &(K, P)) -> bool {
+        self.0.before(&a.1, &b.1)
+    }
+}
@@ -0,0 +1,973 @@
+//! A lock-free priority queue, after Lindén and Jonsson, "A Skiplist-Based Concurrent Priority
+//! Queue with Minimal Memory Contention" (2013).
+//!
+//! The elements are kept in a skip list sorted by the comparator. [`take_front`] claims the first
+//! element by setting the low bit of the pointer to it, so the taken elements form a prefix of
+//! the bottom level, and only once that prefix is long enough does one thread unlink it from the
+//! head. Most calls to [`take_front`] therefore do one atomic operation per taken element they
+//! walk past, and none on the head.
+//!
+//! Unlinked nodes are freed with a simple form of epoch-based reclamation. Each operation counts
+//! itself in the current epoch, and the epoch only moves on once no operation from the one before
+//! it is still in progress. A node unlinked in one epoch can then be freed two epochs later, since
+//! every operation that could have reached it has finished.
+//!
+//! [`take_front`]: ConcurrentPriorityQueue::take_front
+
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+use std::boxed::Box;
+
+#[cfg(all(test, loom))]
+use loom::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize};
+#[cfg(not(all(test, loom)))]
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize};
+
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::{Comparator, MinOrder};
+
+/// The most levels a node can have. Loom explores every interleaving, so it gets a tiny list.
+#[cfg(not(all(test, loom)))]
+const MAX_HEIGHT: usize = 32;
+#[cfg(all(test, loom))]
+const MAX_HEIGHT: usize = 2;
+
+/// How many taken nodes can pile up at the front before they are unlinked.
+#[cfg(not(all(test, loom)))]
+const BOUND_OFFSET: usize = 32;
+#[cfg(all(test, loom))]
+const BOUND_OFFSET: usize = 1;
+
+/// The low bit of a `next[0]` pointer, set once the node it points to has been taken.
+const TAKEN: usize = 1;
+
+/// The states of a node's insertion. A node that is taken and unlinked while it is still being
+/// inserted is orphaned, and then retired by the thread inserting it rather than the one that
+/// unlinked it, along with the nodes after it.
+const INSERTING: u8 = 0;
+const LINKED: u8 = 1;
+const ORPHANED: u8 = 2;
+
+struct Node<K, V> {
+    /// Uninitialized in the head only. Never moved out, since other threads may be comparing
+    /// against it, and dropped when the node is freed.
+    key: MaybeUninit<K>,
+    /// Uninitialized in the head only. Moved out by the thread that takes the node, or dropped
+    /// when the node is freed if it was never taken.
+    value: MaybeUninit<V>,
+    /// Addresses of the next node at each level, or 0 at the end of the list.
+    next: Box<[AtomicUsize]>,
+    /// Set once the node has been taken, so that searches on the upper levels can skip it. The
+    /// pointer to it on the bottom level is marked first.
+    taken: AtomicBool,
+    /// One of `INSERTING`, `LINKED` and `ORPHANED`.
+    state: AtomicU8,
+    /// Address of the node up to which the thread inserting this one retires nodes, if it is
+    /// orphaned.
+    orphan_end: AtomicUsize,
+    /// Address of the next node in the list of unlinked nodes waiting to be freed.
+    retired_next: AtomicUsize,
+}
+
+impl<K, V> Node<K, V> {
+    fn alloc(key: MaybeUninit<K>, value: MaybeUninit<V>, height: usize, state: u8) -> *mut Self {
+        let node = Box::new(Node {
+            key,
+            value,
+            next: (0..height).map(|_| AtomicUsize::new(0)).collect(),
+            taken: AtomicBool::new(false),
+            state: AtomicU8::new(state),
+            orphan_end: AtomicUsize::new(0),
+            retired_next: AtomicUsize::new(0),
+        });
+        Box::into_raw(node)
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a live node other than the head, and no other thread may be using it.
+    unsafe fn free(node: *mut Node<K, V>) {
+        // SAFETY: guaranteed by the caller.
+        unsafe {
+            (*node).key.assume_init_drop();
+            if !(*node).taken.load(SeqCst) {
+                (*node).value.assume_init_drop();
+            }
+            drop(Box::from_raw(node));
+        }
+    }
+
+    fn key(&self) -> &K {
+        // SAFETY: only the head is uninitialized, and it is never compared.
+        unsafe { self.key.assume_init_ref() }
+    }
+}
+
+fn addr<K, V>(node: *mut Node<K, V>) -> usize {
+    node.expose_provenance()
+}
+
+fn node<K, V>(addr: usize) -> *mut Node<K, V> {
+    ptr::with_exposed_provenance_mut(addr & !TAKEN)
+}
+
+fn is_taken(addr: usize) -> bool {
+    addr & TAKEN != 0
+}
+
+/// A lock-free priority queue that can be shared between threads.
+///
+/// [`insert`] and [`take_front`] take `&self` and never block each other. Elements that compare
+/// equal come out in an unspecified order.
+///
+/// Each element is a key, which the comparator orders, and a value. [`take_front`] moves the
+/// value of the front element out and returns it, so values need not be `Clone`. The key stays in
+/// place, since other threads may still be comparing against it, and is dropped along with its
+/// node once every operation that might have seen it has finished. Put the priority in the value
+/// as well if the caller needs it back.
+///
+/// Under low contention, a [`SyncPriorityQueue`] is usually faster; run
+/// `cargo bench --bench concurrent` to compare them.
+///
+/// [`insert`]: ConcurrentPriorityQueue::insert
+/// [`take_front`]: ConcurrentPriorityQueue::take_front
+/// [`SyncPriorityQueue`]: crate::SyncPriorityQueue
+pub struct ConcurrentPriorityQueue<K, V, F = MinOrder> {
+    head: *mut Node<K, V>,
+    cmp: F,
+    epoch: AtomicUsize,
+    /// The number of operations in progress that started in an even and in an odd epoch.
+    active: [AtomicUsize; 2],
+    /// Addresses of the first unlinked nodes waiting to be freed, by the parity of the epoch they
+    /// were unlinked in.
+    retired: [AtomicUsize; 2],
+    marker: PhantomData<(K, V)>,
+}
+
+// SAFETY: elements are inserted on one thread and moved out or dropped on others, and the
+// comparator is called from every thread that shares the queue.
+unsafe impl<K: Send, V: Send, F: Send> Send for ConcurrentPriorityQueue<K, V, F> {}
+// SAFETY: as above, and the keys are read from several threads at once. Each value is only ever
+// moved out by the one thread that takes its node.
+unsafe impl<K: Send + Sync, V: Send, F: Sync> Sync for ConcurrentPriorityQueue<K, V, F> {}
+
+impl<K: PartialOrd, V> ConcurrentPriorityQueue<K, V> {
+    pub fn new() -> Self {
+        Self::with_comparator(MinOrder)
+    }
+}
+
+impl<K: PartialOrd, V> Default for ConcurrentPriorityQueue<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, F: Fn(&K, &K) -> bool> ConcurrentPriorityQueue<K, V, F> {
+    pub fn with_ordering(ordering: F) -> Self {
+        Self::with_comparator(ordering)
+    }
+}
+
+impl<K, V, F: Comparator<K>> ConcurrentPriorityQueue<K, V, F> {
+    pub fn with_comparator(cmp: F) -> Self {
+        Self {
+            head: Node::alloc(
+                MaybeUninit::uninit(),
+                MaybeUninit::uninit(),
+                MAX_HEIGHT,
+                LINKED,
+            ),
+            cmp,
+            epoch: AtomicUsize::new(0),
+            active: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            retired: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            marker: PhantomData,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let _active = self.enter();
+        let height = random_height();
+        let new = Node::alloc(
+            MaybeUninit::new(key),
+            MaybeUninit::new(value),
+            height,
+            INSERTING,
+        );
+        // Frees the node if the comparator panics before it is linked.
+        let mut guard = InsertGuard {
+            queue: self,
+            node: new,
+            linked: false,
+        };
+        // SAFETY: a node reachable from the head is only freed once every operation that started
+        // before it was unlinked has finished, and this one has not.
+        unsafe {
+            let new_ref = &*new;
+            let mut preds = [self.head; MAX_HEIGHT];
+            let mut succs = [ptr::null_mut(); MAX_HEIGHT];
+            let mut del = self.locate(new_ref.key(), &mut preds, &mut succs);
+            loop {
+                new_ref.next[0].store(addr(succs[0]), SeqCst);
+                let linked = (*preds[0]).next[0]
+                    .compare_exchange(addr(succs[0]), addr(new), SeqCst, SeqCst)
+                    .is_ok();
+                if linked {
+                    break;
+                }
+                del = self.locate(new_ref.key(), &mut preds, &mut succs);
+            }
+            guard.linked = true;
+
+            let mut i = 1;
+            while i < height {
+                new_ref.next[i].store(addr(succs[i]), SeqCst);
+                // Stop if the new node, or the one it would point to, has been taken. Along with
+                // the check after a failed CAS, this keeps every level in the bottom level's
+                // order, so a node unlinked from the bottom level is never reachable from one that
+                // is still in the list.
+                if is_taken(new_ref.next[0].load(SeqCst))
+                    || (!succs[i].is_null() && is_taken((*succs[i]).next[0].load(SeqCst)))
+                    || (!del.is_null() && del == succs[i])
+                {
+                    break;
+                }
+                let linked = (*preds[i]).next[i]
+                    .compare_exchange(addr(succs[i]), addr(new), SeqCst, SeqCst)
+                    .is_ok();
+                if linked {
+                    i += 1;
+                } else {
+                    del = self.locate(new_ref.key(), &mut preds, &mut succs);
+                    if succs[0] != new {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if there are no elements. Other threads may change that as soon as this
+    /// returns.
+    pub fn is_empty(&self) -> bool {
+        let _active = self.enter();
+        let mut x = self.head;
+        loop {
+            // SAFETY: as in `insert`.
+            let next = unsafe { (*x).next[0].load(SeqCst) };
+            if node::<K, V>(next).is_null() {
+                return true;
+            }
+            if !is_taken(next) {
+                return false;
+            }
+            x = node(next);
+        }
+    }
+
+    /// Finds the last node before `key` and the node after it at each level, skipping taken
+    /// nodes, and returns the last taken node seen on the bottom level.
+    ///
+    /// # Safety
+    ///
+    /// The caller must have entered an operation.
+    unsafe fn locate(
+        &self,
+        key: &K,
+        preds: &mut [*mut Node<K, V>; MAX_HEIGHT],
+        succs: &mut [*mut Node<K, V>; MAX_HEIGHT],
+    ) -> *mut Node<K, V> {
+        let mut del = ptr::null_mut();
+        let mut x = self.head;
+        for i in (0..MAX_HEIGHT).rev() {
+            // SAFETY: guaranteed by the caller.
+            unsafe {
+                let mut next = (*x).next[i].load(SeqCst);
+                loop {
+                    let cur = node::<K, V>(next);
+                    if cur.is_null() {
+                        break;
+                    }
+                    let cur_taken = i == 0 && is_taken(next);
+                    let skip = cur_taken
+                        || (*cur).taken.load(SeqCst)
+                        || is_taken((*cur).next[0].load(SeqCst))
+                        || self.cmp.before((*cur).key(), key);
+                    if !skip {
+                        break;
+                    }
+                    if cur_taken {
+                        del = cur;
+                    }
+                    x = cur;
+                    next = (*x).next[i].load(SeqCst);
+                }
+                preds[i] = x;
+                succs[i] = node(next);
+            }
+        }
+        del
+    }
+
+    /// Removes the front element and returns its value.
+    pub fn take_front(&self) -> Option<V> {
+        let _active = self.enter();
+        // SAFETY: as in `insert`.
+        unsafe {
+            let head = &*self.head;
+            let obs_head = head.next[0].load(SeqCst);
+            let mut x = self.head;
+            let mut offset = 0;
+            // Claim the first node that is not taken yet, or stop at the end of the list.
+            let value = loop {
+                let next = (*x).next[0].load(SeqCst);
+                if node::<K, V>(next).is_null() {
+                    break None;
+                }
+                let next = (*x).next[0].fetch_or(TAKEN, SeqCst);
+                offset += 1;
+                x = node(next);
+                if !is_taken(next) {
+                    (*x).taken.store(true, SeqCst);
+                    // SAFETY: only the thread that took the node reads its value, and the node
+                    // is marked as taken so that it is not dropped again when it is freed.
+                    break Some((*x).value.assume_init_read());
+                }
+            };
+
+            // Once enough taken nodes have piled up, unlink the ones before the last node reached
+            // from the head, even if the queue turned out to be empty. Only the thread whose view
+            // of the head is still current does so.
+            if offset <= BOUND_OFFSET || head.next[0].load(SeqCst) != obs_head {
+                return value;
+            }
+            let moved = head.next[0]
+                .compare_exchange(obs_head, addr(x) | TAKEN, SeqCst, SeqCst)
+                .is_ok();
+            if moved {
+                self.retire(node(obs_head), x);
+            }
+            value
+        }
+    }
+}
+
+impl<K, V, F> Drop for ConcurrentPriorityQueue<K, V, F> {
+    fn drop(&mut self) {
+        // SAFETY: no other thread can be using the queue, so every node in the list and every
+        // retired node can be freed, each exactly once.
+        unsafe {
+            let mut cur = node::<K, V>((*self.head).next[0].load(SeqCst));
+            while !cur.is_null() {
+                let next = node((*cur).next[0].load(SeqCst));
+                Node::free(cur);
+                cur = next;
+            }
+            for retired in &self.retired {
+                free_retired(node::<K, V>(retired.load(SeqCst)));
+            }
+            drop(Box::from_raw(self.head));
+        }
+    }
+}
+
+/// Counts an operation as in progress in the epoch it started in.
+struct Active<'a, K, V, F> {
+    queue: &'a ConcurrentPriorityQueue<K, V, F>,
+    active: &'a AtomicUsize,
+}
+
+impl<K, V, F> Drop for Active<'_, K, V, F> {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, SeqCst);
+        self.queue.collect();
+    }
+}
+
+impl<K, V, F> ConcurrentPriorityQueue<K, V, F> {
+    /// Unlinks the taken nodes from the head at every level above the bottom.
+    ///
+    /// # Safety
+    ///
+    /// The caller must have entered an operation.
+    unsafe fn restructure(&self) {
+        let mut pred = self.head;
+        let mut i = MAX_HEIGHT - 1;
+        // SAFETY: guaranteed by the caller.
+        unsafe {
+            while i > 0 {
+                let h = (*self.head).next[i].load(SeqCst);
+                let h_node = node::<K, V>(h);
+                if h_node.is_null() || !is_taken((*h_node).next[0].load(SeqCst)) {
+                    i -= 1;
+                    continue;
+                }
+                let mut cur = node::<K, V>((*pred).next[i].load(SeqCst));
+                while !cur.is_null() && is_taken((*cur).next[0].load(SeqCst)) {
+                    pred = cur;
+                    cur = node((*pred).next[i].load(SeqCst));
+                }
+                let replaced = (*self.head).next[i]
+                    .compare_exchange(h, addr(cur), SeqCst, SeqCst)
+                    .is_ok();
+                if replaced {
+                    i -= 1;
+                }
+            }
+        }
+    }
+
+    /// Retires the nodes from `first` up to, but not including, `end`, once they have been
+    /// unlinked from the bottom level.
+    ///
+    /// # Safety
+    ///
+    /// The caller must have entered an operation, and just unlinked the nodes.
+    unsafe fn retire(&self, first: *mut Node<K, V>, end: *mut Node<K, V>) {
+        let mut chain: (*mut Node<K, V>, *mut Node<K, V>) = (ptr::null_mut(), ptr::null_mut());
+        // SAFETY: guaranteed by the caller.
+        unsafe {
+            let mut cur = first;
+            while cur != end {
+                // A node that is still being inserted may have the head pointed at it again, and
+                // with it the nodes after it, so those are left to the thread inserting it.
+                let cur_ref = &*cur;
+                if cur_ref.state.load(SeqCst) == INSERTING {
+                    cur_ref.orphan_end.store(addr(end), SeqCst);
+                    let orphaned = cur_ref
+                        .state
+                        .compare_exchange(INSERTING, ORPHANED, SeqCst, SeqCst)
+                        .is_ok();
+                    if orphaned {
+                        break;
+                    }
+                }
+                if chain.0.is_null() {
+                    chain.0 = cur;
+                } else {
+                    (*chain.1).retired_next.store(addr(cur), SeqCst);
+                }
+                chain.1 = cur;
+                cur = node(cur_ref.next[0].load(SeqCst));
+            }
+            self.restructure();
+            if !chain.0.is_null() {
+                self.push_retired(chain.0, chain.1);
+            }
+        }
+    }
+
+    /// Pushes a chain of nodes linked by `retired_next` onto the nodes waiting to be freed, in
+    /// the current epoch.
+    ///
+    /// # Safety
+    ///
+    /// The nodes must be unreachable from the head, and `last` must be reachable from `first`
+    /// through `retired_next`.
+    unsafe fn push_retired(&self, first: *mut Node<K, V>, last: *mut Node<K, V>) {
+        let retired = &self.retired[self.epoch.load(SeqCst) % 2];
+        let mut head = retired.load(SeqCst);
+        loop {
+            // SAFETY: guaranteed by the caller.
+            unsafe { (*last).retired_next.store(head, SeqCst) };
+            match retired.compare_exchange(head, addr(first), SeqCst, SeqCst) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    fn enter(&self) -> Active<'_, K, V, F> {
+        loop {
+            let epoch = self.epoch.load(SeqCst);
+            let active = &self.active[epoch % 2];
+            active.fetch_add(1, SeqCst);
+            // Once the epoch has moved on, operations from the one before the new epoch must not
+            // start, or the epoch could move on again while they are running.
+            if self.epoch.load(SeqCst) == epoch {
+                return Active {
+                    queue: self,
+                    active,
+                };
+            }
+            active.fetch_sub(1, SeqCst);
+        }
+    }
+
+    /// Moves on to the next epoch if no operation from the previous one is in progress, and frees
+    /// the nodes that were unlinked in it.
+    fn collect(&self) {
+        let epoch = self.epoch.load(SeqCst);
+        let previous = (epoch + 1) % 2;
+        if self.active[previous].load(SeqCst) != 0
+            || (self.retired[0].load(SeqCst) == 0 && self.retired[1].load(SeqCst) == 0)
+        {
+            return;
+        }
+        // Every operation that started before these nodes were unlinked is from the previous
+        // epoch or earlier, and has finished.
+        let retired = node::<K, V>(self.retired[previous].swap(0, SeqCst));
+        let moved = self
+            .epoch
+            .compare_exchange(epoch, epoch + 1, SeqCst, SeqCst)
+            .is_ok();
+        // SAFETY: see above. If another thread moved the epoch on first, the nodes are put back
+        // to be freed later.
+        unsafe {
+            if moved {
+                free_retired(retired);
+            } else if !retired.is_null() {
+                let mut last = retired;
+                loop {
+                    let next = node::<K, V>((*last).retired_next.load(SeqCst));
+                    if next.is_null() {
+                        break;
+                    }
+                    last = next;
+                }
+                self.push_retired(retired, last);
+            }
+        }
+    }
+}
+
+/// Frees a chain of nodes linked by `retired_next`.
+///
+/// # Safety
+///
+/// No other thread may be using the nodes.
+unsafe fn free_retired<K, V>(mut cur: *mut Node<K, V>) {
+    while !cur.is_null() {
+        // SAFETY: guaranteed by the caller.
+        unsafe {
+            let next = node((*cur).retired_next.load(SeqCst));
+            Node::free(cur);
+            cur = next;
+        }
+    }
+}
+
+struct InsertGuard<'a, K, V, F> {
+    queue: &'a ConcurrentPriorityQueue<K, V, F>,
+    node: *mut Node<K, V>,
+    linked: bool,
+}
+
+impl<K, V, F> Drop for InsertGuard<'_, K, V, F> {
+    fn drop(&mut self) {
+        if self.linked {
+            // SAFETY: the operation inserting the node is in progress.
+            unsafe {
+                let linked = (*self.node)
+                    .state
+                    .compare_exchange(INSERTING, LINKED, SeqCst, SeqCst)
+                    .is_ok();
+                if !linked {
+                    // The node was unlinked while its upper levels were being linked. `retire`
+                    // points the head past it again before retiring it.
+                    let end = node((*self.node).orphan_end.load(SeqCst));
+                    self.queue.retire(self.node, end);
+                }
+            }
+        } else {
+            // SAFETY: the node was never shared.
+            unsafe { Node::free(self.node) };
+        }
+    }
+}
+
+#[cfg(not(all(test, loom)))]
+std::thread_local! {
+    static STATE: Cell<u64> = const { Cell::new(0) };
+}
+#[cfg(all(test, loom))]
+loom::thread_local! {
+    static STATE: Cell<u64> = Cell::new(0);
+}
+
+/// Picks a height between 1 and `MAX_HEIGHT`, each one half as likely as the one before.
+fn random_height() -> usize {
+    STATE.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            x = seed(state);
+        }
+        // xorshift64
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x.trailing_ones() as usize + 1).min(MAX_HEIGHT)
+    })
+}
+
+/// Seeds each thread differently, so that the threads do not build the same towers.
+#[cfg(not(all(test, loom)))]
+fn seed(state: &Cell<u64>) -> u64 {
+    let addr = state as *const Cell<u64> as u64;
+    addr.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1
+}
+
+/// Loom replays every execution, so the heights have to be the same each time.
+#[cfg(all(test, loom))]
+fn seed(_: &Cell<u64>) -> u64 {
+    0x2545_f491_4f6c_dd1d
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    /// One call on a queue of distinct `u32`s, with the clock readings taken before and after it.
+    #[derive(Clone, Copy, Debug)]
+    struct Call {
+        start: usize,
+        end: usize,
+        op: Op,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Insert(u32),
+        TakeFront(Option<u32>),
+    }
+
+    /// Checks that the calls can be put in an order that respects real time, in which every
+    /// `take_front` returns what a sequential min-queue would. Calls that overlap may go in
+    /// either order.
+    fn is_linearizable(initial: &[u32], calls: &[Call]) -> bool {
+        fn search(queue: &mut BTreeSet<u32>, calls: &[Call], done: &mut Vec<bool>) -> bool {
+            if done.iter().all(|&d| d) {
+                return true;
+            }
+            // A call can go next if no pending call ended before it started.
+            let first_end = (0..calls.len())
+                .filter(|&i| !done[i])
+                .map(|i| calls[i].end)
+                .min()
+                .unwrap();
+            for i in 0..calls.len() {
+                if done[i] || calls[i].start > first_end {
+                    continue;
+                }
+                match calls[i].op {
+                    Op::Insert(x) => {
+                        queue.insert(x);
+                    }
+                    Op::TakeFront(result) => {
+                        if queue.first().copied() != result {
+                            continue;
+                        }
+                        if let Some(x) = result {
+                            queue.remove(&x);
+                        }
+                    }
+                }
+                done[i] = true;
+                if search(queue, calls, done) {
+                    return true;
+                }
+                done[i] = false;
+                match calls[i].op {
+                    Op::Insert(x) => {
+                        queue.remove(&x);
+                    }
+                    Op::TakeFront(result) => queue.extend(result),
+                }
+            }
+            false
+        }
+
+        let mut queue = initial.iter().copied().collect();
+        search(&mut queue, calls, &mut vec![false; calls.len()])
+    }
+
+    #[cfg(not(loom))]
+    mod std_threads {
+        use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+        use std::sync::{Arc, Barrier, Mutex};
+        use std::thread;
+
+        use super::{is_linearizable, Call, Op};
+        use crate::concurrent::BOUND_OFFSET;
+        use crate::test_util::xorshift;
+        use crate::{Comparator, ConcurrentPriorityQueue, MaxOrder};
+
+        #[test]
+        fn single_thread() {
+            let queue = ConcurrentPriorityQueue::new();
+            assert!(queue.is_empty());
+            assert_eq!(queue.take_front(), None);
+            let mut next = xorshift(0x2545_f491);
+            let mut expected = Vec::new();
+            for _ in 0..10_000 {
+                let x = next() % 1000;
+                queue.insert(x, x);
+                expected.push(x);
+            }
+            assert!(!queue.is_empty());
+            expected.sort();
+            let taken: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+            assert_eq!(taken, expected);
+            assert!(queue.is_empty());
+        }
+
+        #[test]
+        fn custom_ordering() {
+            let queue =
+                ConcurrentPriorityQueue::with_ordering(|a: &&str, b: &&str| a.len() < b.len());
+            for s in ["ccc", "a", "dddd", "bb"] {
+                queue.insert(s, s);
+            }
+            let taken: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+            assert_eq!(taken, ["a", "bb", "ccc", "dddd"]);
+
+            let queue = ConcurrentPriorityQueue::with_comparator(MaxOrder);
+            for x in [3, 9, 1] {
+                queue.insert(x, x);
+            }
+            assert_eq!(queue.take_front(), Some(9));
+        }
+
+        #[test]
+        fn values_need_not_be_clone() {
+            let queue = ConcurrentPriorityQueue::new();
+            for priority in [3, 1, 2] {
+                let job: Box<dyn FnOnce() -> u32 + Send> = Box::new(move || priority * 10);
+                queue.insert(priority, job);
+            }
+            let results: Vec<_> = std::iter::from_fn(|| queue.take_front())
+                .map(|job| job())
+                .collect();
+            assert_eq!(results, [10, 20, 30]);
+        }
+
+        #[test]
+        fn drops_every_element_once() {
+            let keys = Arc::new(());
+            let values = Arc::new(());
+            let queue = ConcurrentPriorityQueue::with_ordering(|a: &(u32, Arc<()>), b| a.0 < b.0);
+            for i in 0..500 {
+                queue.insert((i, Arc::clone(&keys)), Arc::clone(&values));
+            }
+            for _ in 0..300 {
+                drop(queue.take_front());
+            }
+            // Taken values are moved out straight away. Nothing else is running, so the only
+            // taken keys still alive are the ones that have not been unlinked yet.
+            assert_eq!(Arc::strong_count(&values) - 1, 200);
+            let alive = Arc::strong_count(&keys) - 1;
+            assert!((200..=200 + BOUND_OFFSET).contains(&alive), "{alive}");
+            drop(queue);
+            assert_eq!(Arc::strong_count(&keys), 1);
+            assert_eq!(Arc::strong_count(&values), 1);
+        }
+
+        /// Has several threads insert distinct elements and take most of them back, and checks
+        /// that each element comes out exactly once.
+        fn insert_and_take_concurrently<F: Comparator<u32> + Sync>(
+            queue: ConcurrentPriorityQueue<u32, u32, F>,
+        ) {
+            const THREADS: u32 = 8;
+            const PER_THREAD: u32 = if cfg!(miri) { 200 } else { 20_000 };
+
+            let start = Barrier::new(THREADS as usize);
+            let mut all: Vec<_> = thread::scope(|s| {
+                let threads: Vec<_> = (0..THREADS)
+                    .map(|t| {
+                        let (queue, start) = (&queue, &start);
+                        s.spawn(move || {
+                            start.wait();
+                            let mut taken = Vec::new();
+                            for i in 0..PER_THREAD {
+                                let x = i * THREADS + t;
+                                queue.insert(x, x);
+                                if i % 3 != 0 {
+                                    taken.extend(queue.take_front());
+                                }
+                            }
+                            taken
+                        })
+                    })
+                    .collect();
+                threads
+                    .into_iter()
+                    .flat_map(|t| t.join().unwrap())
+                    .collect()
+            });
+            all.extend(std::iter::from_fn(|| queue.take_front()));
+            all.sort();
+            assert_eq!(all, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn many_producers_and_consumers() {
+            insert_and_take_concurrently(ConcurrentPriorityQueue::new());
+        }
+
+        #[test]
+        fn many_equal_elements() {
+            insert_and_take_concurrently(ConcurrentPriorityQueue::with_ordering(
+                |a: &u32, b: &u32| a % 4 < b % 4,
+            ));
+        }
+
+        #[test]
+        fn linearizable() {
+            const THREADS: usize = 3;
+            const CALLS: u32 = 4;
+
+            for round in 0..300 {
+                let queue = Arc::new(ConcurrentPriorityQueue::new());
+                let initial = [5, 15];
+                for x in initial {
+                    queue.insert(x, x);
+                }
+                let clock = Arc::new(AtomicUsize::new(0));
+                let calls = Arc::new(Mutex::new(Vec::new()));
+                let start = Arc::new(Barrier::new(THREADS));
+                let threads: Vec<_> = (0..THREADS)
+                    .map(|t| {
+                        let (queue, clock) = (Arc::clone(&queue), Arc::clone(&clock));
+                        let (calls, start) = (Arc::clone(&calls), Arc::clone(&start));
+                        thread::spawn(move || {
+                            start.wait();
+                            for i in 0..CALLS {
+                                let begin = clock.fetch_add(1, SeqCst);
+                                let op = if (round + t + i as usize).is_multiple_of(2) {
+                                    let x = (i * THREADS as u32 + t as u32) * 2;
+                                    queue.insert(x, x);
+                                    Op::Insert(x)
+                                } else {
+                                    Op::TakeFront(queue.take_front())
+                                };
+                                let end = clock.fetch_add(1, SeqCst);
+                                calls.lock().unwrap().push(Call {
+                                    start: begin,
+                                    end,
+                                    op,
+                                });
+                            }
+                        })
+                    })
+                    .collect();
+                for thread in threads {
+                    thread.join().unwrap();
+                }
+                let calls = calls.lock().unwrap();
+                assert!(is_linearizable(&initial, &calls), "{calls:?}");
+            }
+        }
+
+        #[test]
+        fn checker_rejects_wrong_histories() {
+            let call = |start, end, op| Call { start, end, op };
+            // Taking 5 while 1 was already in the queue.
+            let calls = [
+                call(0, 1, Op::Insert(1)),
+                call(2, 3, Op::TakeFront(Some(5))),
+            ];
+            assert!(!is_linearizable(&[5], &calls));
+            // The same calls overlapping can be ordered either way.
+            let calls = [
+                call(0, 2, Op::Insert(1)),
+                call(1, 3, Op::TakeFront(Some(5))),
+            ];
+            assert!(is_linearizable(&[5], &calls));
+        }
+    }
+    #[cfg(loom)]
+    mod loom_model {
+        use loom::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+        use loom::sync::{Arc, Mutex};
+        use loom::thread;
+
+        use super::{is_linearizable, Call, Op};
+        use crate::ConcurrentPriorityQueue;
+
+        /// Runs `ops` on two threads against a queue holding `initial`, in every interleaving
+        /// loom can find, and checks each history.
+        fn check(initial: &'static [u32], ops: [&'static [Option<u32>]; 2]) {
+            // Without a bound the search takes hours. Most bugs need only a couple of preemptions.
+            let mut model = loom::model::Builder::new();
+            model.preemption_bound = Some(4);
+            model.check(move || {
+                let queue = Arc::new(ConcurrentPriorityQueue::new());
+                for &x in initial {
+                    queue.insert(x, x);
+                }
+                let clock = Arc::new(AtomicUsize::new(0));
+                let calls = Arc::new(Mutex::new(Vec::new()));
+                let threads: Vec<_> = ops
+                    .into_iter()
+                    .map(|ops| {
+                        let (queue, clock) = (Arc::clone(&queue), Arc::clone(&clock));
+                        let calls = Arc::clone(&calls);
+                        thread::spawn(move || {
+                            for &op in ops {
+                                let start = clock.fetch_add(1, SeqCst);
+                                let op = match op {
+                                    Some(x) => {
+                                        queue.insert(x, x);
+                                        Op::Insert(x)
+                                    }
+                                    None => Op::TakeFront(queue.take_front()),
+                                };
+                                let end = clock.fetch_add(1, SeqCst);
+                                calls.lock().unwrap().push(Call { start, end, op });
+                            }
+                        })
+                    })
+                    .collect();
+                for thread in threads {
+                    thread.join().unwrap();
+                }
+                let calls = calls.lock().unwrap();
+                assert!(is_linearizable(initial, &calls), "{calls:?}");
+            });
+        }
+
+        #[test]
+        fn insert_and_take() {
+            check(&[2], [&[Some(1)], &[None, None]]);
+        }
+
+        #[test]
+        fn two_takes() {
+            check(&[1, 2, 3], [&[None, None], &[None]]);
+        }
+
+        #[test]
+        fn two_inserts() {
+            check(&[], [&[Some(2), None], &[Some(1)]]);
+        }
+
+        #[test]
+        fn equal_elements() {
+            let mut model = loom::model::Builder::new();
+            model.preemption_bound = Some(3);
+            model.check(|| {
+                let queue = Arc::new(ConcurrentPriorityQueue::with_ordering(
+                    |a: &u32, b: &u32| a / 10 < b / 10,
+                ));
+                queue.insert(10, 10);
+                let inserter = {
+                    let queue = Arc::clone(&queue);
+                    thread::spawn(move || {
+                        queue.insert(11, 11);
+                        queue.insert(12, 12);
+                    })
+                };
+                let mut taken: Vec<_> = (0..2).filter_map(|_| queue.take_front()).collect();
+                inserter.join().unwrap();
+                taken.extend(std::iter::from_fn(|| queue.take_front()));
+                taken.sort();
+                assert_eq!(taken, [10, 11, 12]);
+            });
+        }
+    }
+}
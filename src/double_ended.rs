@@ -0,0 +1,367 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use crate::{Comparator, MinOrder};
+
+/// A priority queue that can take elements from both ends of the ordering.
+///
+/// This is a min-max heap: the elements on even levels of the tree come before all of their
+/// descendants, and the elements on odd levels come after all of their descendants. The front
+/// element is therefore the root, and the back element is one of its two children, so
+/// [`take_front`], [`take_back`] and [`insert`] all run in O(log n).
+///
+/// If the comparator panics, the panic propagates and no element other than the one being taken
+/// is lost. The queue is [poisoned], since the panic may have left the heap out of order: the next
+/// operation that needs the order rebuilds the heap first, and [`peek_front`] and [`peek_back`]
+/// fall back to a linear scan until then.
+///
+/// [`take_front`]: DoubleEndedPriorityQueue::take_front
+/// [`take_back`]: DoubleEndedPriorityQueue::take_back
+/// [`insert`]: DoubleEndedPriorityQueue::insert
+/// [`peek_front`]: DoubleEndedPriorityQueue::peek_front
+/// [`peek_back`]: DoubleEndedPriorityQueue::peek_back
+/// [poisoned]: DoubleEndedPriorityQueue::is_poisoned
+pub struct DoubleEndedPriorityQueue<T, F = MinOrder> {
+    heap: Vec<T>,
+    cmp: F,
+    /// Set while the comparator is running, so that it stays set if the comparator panics.
+    poisoned: bool,
+}
+
+impl<T: PartialOrd> DoubleEndedPriorityQueue<T> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T: PartialOrd> Default for DoubleEndedPriorityQueue<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for DoubleEndedPriorityQueue<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.heap).finish()
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> DoubleEndedPriorityQueue<T, F> {
+    pub fn with_ordering(data: Vec<T>, ordering: F) -> Self {
+        Self::with_comparator(data, ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> DoubleEndedPriorityQueue<T, F> {
+    pub fn with_comparator(data: Vec<T>, cmp: F) -> Self {
+        let mut queue = Self {
+            heap: data,
+            cmp,
+            poisoned: false,
+        };
+        queue.heapify();
+        queue
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        self.repair();
+        if self.heap.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    pub fn take_back(&mut self) -> Option<T> {
+        self.repair();
+        let i = self.back_index()?;
+        Some(self.remove_at(i))
+    }
+
+    pub fn insert(&mut self, element: T) {
+        self.repair();
+        self.heap.push(element);
+        self.poisoned = true;
+        self.push_up(self.heap.len() - 1);
+        self.poisoned = false;
+    }
+
+    /// Returns the front element, falling back to a linear scan if the queue is poisoned.
+    pub fn peek_front(&self) -> Option<&T> {
+        if self.poisoned {
+            return self.scan(|a, b| self.cmp.before(a, b));
+        }
+        self.heap.first()
+    }
+
+    /// Returns the back element, falling back to a linear scan if the queue is poisoned.
+    pub fn peek_back(&self) -> Option<&T> {
+        if self.poisoned {
+            return self.scan(|a, b| self.cmp.before(b, a));
+        }
+        self.back_index().map(|i| &self.heap[i])
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.poisoned = false;
+    }
+
+    /// Returns `true` if the comparator panicked during an earlier operation, which may have left
+    /// the elements out of heap order.
+    ///
+    /// A poisoned queue still behaves correctly, rebuilding the heap when it is next modified.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Rebuilds the heap if the queue is poisoned, clearing the poison.
+    ///
+    /// If the comparator panics again, the queue stays poisoned.
+    pub fn clear_poison(&mut self) {
+        self.repair();
+    }
+
+    fn repair(&mut self) {
+        if self.poisoned {
+            self.heapify();
+        }
+    }
+
+    /// Returns the element that `first` puts ahead of all the others.
+    fn scan(&self, first: impl Fn(&T, &T) -> bool) -> Option<&T> {
+        self.heap
+            .iter()
+            .reduce(|a, b| if first(b, a) { b } else { a })
+    }
+
+    /// Compares `a` and `b` by the ordering of the level they are on: front levels keep the
+    /// earliest element on top, back levels keep the latest.
+    fn cmp(&self, front_level: bool, a: &T, b: &T) -> bool {
+        if front_level {
+            self.cmp.before(a, b)
+        } else {
+            self.cmp.before(b, a)
+        }
+    }
+
+    fn back_index(&self) -> Option<usize> {
+        match self.heap.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ if self.cmp.before(&self.heap[1], &self.heap[2]) => Some(2),
+            _ => Some(1),
+        }
+    }
+
+    fn remove_at(&mut self, i: usize) -> T {
+        let element = self.heap.swap_remove(i);
+        if i < self.heap.len() {
+            self.poisoned = true;
+            self.push_down(i);
+            self.poisoned = false;
+        }
+        element
+    }
+
+    fn heapify(&mut self) {
+        self.poisoned = true;
+        for i in (0..self.heap.len() / 2).rev() {
+            self.push_down(i);
+        }
+        self.poisoned = false;
+    }
+
+    fn push_up(&mut self, i: usize) {
+        if i == 0 {
+            return;
+        }
+        let front_level = is_front_level(i);
+        let parent = (i - 1) / 2;
+        // The parent is on the opposite kind of level, so if the new element belongs above it, it
+        // belongs among the parent's ancestors on that kind of level.
+        if self.cmp(!front_level, &self.heap[i], &self.heap[parent]) {
+            self.heap.swap(i, parent);
+            self.push_up_levels(!front_level, parent);
+        } else {
+            self.push_up_levels(front_level, i);
+        }
+    }
+
+    /// Sifts the element at `i` up through its grandparents, which are on the same kind of level.
+    fn push_up_levels(&mut self, front_level: bool, mut i: usize) {
+        while i > 2 {
+            let grandparent = (i - 3) / 4;
+            if !self.cmp(front_level, &self.heap[i], &self.heap[grandparent]) {
+                break;
+            }
+            self.heap.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    fn push_down(&mut self, mut i: usize) {
+        let front_level = is_front_level(i);
+        let len = self.heap.len();
+        loop {
+            let first_child = i * 2 + 1;
+            if first_child >= len {
+                return;
+            }
+
+            // Find the earliest (on a front level) or latest (on a back level) of the children
+            // and grandchildren.
+            let first_grandchild = first_child * 2 + 1;
+            let descendants = (first_child..(first_child + 2).min(len))
+                .chain(first_grandchild..(first_grandchild + 4).min(len));
+            let mut m = first_child;
+            for j in descendants.skip(1) {
+                if self.cmp(front_level, &self.heap[j], &self.heap[m]) {
+                    m = j;
+                }
+            }
+
+            if !self.cmp(front_level, &self.heap[m], &self.heap[i]) {
+                return;
+            }
+            self.heap.swap(m, i);
+            if m < first_grandchild {
+                return;
+            }
+            let parent = (m - 1) / 2;
+            if self.cmp(front_level, &self.heap[parent], &self.heap[m]) {
+                self.heap.swap(m, parent);
+            }
+            i = m;
+        }
+    }
+}
+
+fn is_front_level(i: usize) -> bool {
+    (i + 1).ilog2().is_multiple_of(2)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::xorshift;
+    use crate::DoubleEndedPriorityQueue;
+
+    #[test]
+    fn both_ends() {
+        let mut queue = DoubleEndedPriorityQueue::new(vec![5, 1, 9, 3, 7, 2, 8]);
+        assert_eq!(queue.peek_front(), Some(&1));
+        assert_eq!(queue.peek_back(), Some(&9));
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_back(), Some(9));
+        assert_eq!(queue.take_back(), Some(8));
+        assert_eq!(queue.take_front(), Some(2));
+        queue.insert(0);
+        queue.insert(10);
+        assert_eq!(queue.take_back(), Some(10));
+        assert_eq!(queue.take_front(), Some(0));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_back(), Some(7));
+        assert_eq!(queue.take_back(), Some(5));
+        assert_eq!(queue.take_back(), None);
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn custom_ordering() {
+        let mut queue =
+            DoubleEndedPriorityQueue::with_ordering(vec![3, 2, 6, 5, 1, 4], |a, b| a > b);
+        assert_eq!(queue.take_front(), Some(6));
+        assert_eq!(queue.take_back(), Some(1));
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn random_operations() {
+        let mut next = xorshift(0x2545_f491);
+        let data: Vec<u32> = (0..100).map(|_| next() % 500).collect();
+        let mut model = data.clone();
+        model.sort();
+        let mut queue = DoubleEndedPriorityQueue::new(data);
+        for _ in 0..5000 {
+            match next() % 3 {
+                0 => {
+                    let value = next() % 500;
+                    queue.insert(value);
+                    let i = model.partition_point(|&y| y < value);
+                    model.insert(i, value);
+                }
+                1 => {
+                    let expected = (!model.is_empty()).then(|| model.remove(0));
+                    assert_eq!(queue.take_front(), expected);
+                }
+                _ => assert_eq!(queue.take_back(), model.pop()),
+            }
+            assert_eq!(queue.peek_front(), model.first());
+            assert_eq!(queue.peek_back(), model.last());
+        }
+    }
+
+    #[test]
+    fn comparator_panic() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let comparisons = Cell::new(0);
+        let panic_at = Cell::new(0);
+        let cmp = |a: &i32, b: &i32| {
+            comparisons.set(comparisons.get() + 1);
+            if comparisons.get() == panic_at.get() {
+                panic!("comparator panicked");
+            }
+            a < b
+        };
+        for i in 1.. {
+            panic_at.set(0);
+            let mut queue = DoubleEndedPriorityQueue::with_ordering((0..30).collect(), &cmp);
+            comparisons.set(0);
+            panic_at.set(i);
+            let mut taken = Vec::new();
+            let panicked = catch_unwind(AssertUnwindSafe(|| {
+                queue.insert(-1);
+                queue.insert(40);
+                taken.extend(queue.take_front());
+                taken.extend(queue.take_back());
+            }))
+            .is_err();
+            panic_at.set(0);
+            // Picking the back element compares without moving anything, so a panic there
+            // leaves the queue unpoisoned.
+            assert!(panicked || !queue.is_poisoned());
+            if !panicked {
+                break;
+            }
+
+            let mut expected: Vec<_> = (-1..30).chain([40]).collect();
+            expected.retain(|x| !taken.contains(x));
+            let len = queue.len();
+            // A panic during `take_front` or `take_back` drops the element that was being taken.
+            assert!(len == expected.len() || len == expected.len() - 1);
+            let front = queue.peek_front().copied();
+            let back = queue.peek_back().copied();
+            let actual: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+            assert!(!queue.is_poisoned());
+            assert_eq!(actual.len(), len);
+            assert!(
+                actual.windows(2).all(|w| w[0] < w[1]),
+                "panic at comparison {i}"
+            );
+            assert_eq!(actual.first().copied(), front);
+            assert_eq!(actual.last().copied(), back);
+            assert!(actual.iter().all(|x| expected.contains(x)));
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use core::error::Error;
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::TryReserveError;
+
+/// A parent and child in a heap that are out of order.
+///
+/// Returned by [`DaryPriorityQueue::check_heap_invariant`].
+///
+/// [`DaryPriorityQueue::check_heap_invariant`]: crate::DaryPriorityQueue::check_heap_invariant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeapInvariantError {
+    /// Index of the parent in the heap.
+    pub parent: usize,
+    /// Index of the child, which comes before its parent in the queue's ordering.
+    pub child: usize,
+}
+
+impl fmt::Display for HeapInvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heap element {} comes before its parent at index {}",
+            self.child, self.parent
+        )
+    }
+}
+
+impl Error for HeapInvariantError {}
+
+/// The error returned when a queue is asked to hold more elements than its limit allows.
+///
+/// See [`GenericPriorityQueue::set_limit`].
+///
+/// [`GenericPriorityQueue::set_limit`]: crate::GenericPriorityQueue::set_limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    pub(crate) limit: usize,
+}
+
+impl CapacityError {
+    /// Returns the most elements the queue can hold.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "priority queue is limited to {} elements", self.limit)
+    }
+}
+
+impl Error for CapacityError {}
+
+/// The reason a queue could not make room for more elements.
+///
+/// The `Alloc` variant only exists with the `alloc` feature, so matches need a wildcard arm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReserveError {
+    /// The allocator failed, or the capacity overflowed.
+    #[cfg(feature = "alloc")]
+    Alloc(TryReserveError),
+    /// The queue or its storage is at its limit.
+    Capacity(CapacityError),
+}
+
+#[cfg(feature = "alloc")]
+impl From<TryReserveError> for ReserveError {
+    fn from(error: TryReserveError) -> Self {
+        ReserveError::Alloc(error)
+    }
+}
+
+impl From<CapacityError> for ReserveError {
+    fn from(error: CapacityError) -> Self {
+        ReserveError::Capacity(error)
+    }
+}
+
+impl fmt::Display for ReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "alloc")]
+            ReserveError::Alloc(error) => error.fmt(f),
+            ReserveError::Capacity(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for ReserveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "alloc")]
+            ReserveError::Alloc(error) => Some(error),
+            ReserveError::Capacity(error) => Some(error),
+        }
+    }
+}
+
+/// An element that could not be inserted, and the reason why.
+///
+/// Returned by [`GenericPriorityQueue::try_insert`].
+///
+/// [`GenericPriorityQueue::try_insert`]: crate::GenericPriorityQueue::try_insert
+#[derive(Clone, PartialEq, Eq)]
+pub struct TryInsertError<T> {
+    pub(crate) element: T,
+    pub(crate) reason: ReserveError,
+}
+
+impl<T> TryInsertError<T> {
+    /// Returns the element that could not be inserted.
+    pub fn into_element(self) -> T {
+        self.element
+    }
+
+    pub fn reason(&self) -> &ReserveError {
+        &self.reason
+    }
+}
+
+impl<T> fmt::Debug for TryInsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryInsertError")
+            .field("reason", &self.reason)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for TryInsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not insert element: {}", self.reason)
+    }
+}
+
+impl<T> Error for TryInsertError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.reason)
+    }
+}
+
+/// The error returned by [`SyncPriorityQueue::push`]. Gives back the element.
+///
+/// [`SyncPriorityQueue::push`]: crate::SyncPriorityQueue::push
+#[cfg(feature = "std")]
+#[derive(Clone, PartialEq, Eq)]
+pub enum PushError<T> {
+    /// The queue is closed.
+    Closed(T),
+    /// The queue is at its [limit], or could not allocate room for the element.
+    ///
+    /// [limit]: crate::GenericPriorityQueue::set_limit
+    Full(TryInsertError<T>),
+}
+
+#[cfg(feature = "std")]
+impl<T> PushError<T> {
+    /// Returns the element that could not be pushed.
+    pub fn into_element(self) -> T {
+        match self {
+            PushError::Closed(element) => element,
+            PushError::Full(error) => error.into_element(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for PushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::Closed(_) => f.debug_tuple("Closed").finish_non_exhaustive(),
+            PushError::Full(error) => f.debug_tuple("Full").field(error).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Display for PushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::Closed(_) => f.write_str("pushing to a closed queue"),
+            PushError::Full(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Error for PushError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PushError::Closed(_) => None,
+            PushError::Full(error) => error.source(),
+        }
+    }
+}
+
+/// The error returned by [`SyncPriorityQueue::pop`] when the queue is closed and empty.
+///
+/// [`SyncPriorityQueue::pop`]: crate::SyncPriorityQueue::pop
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClosedError;
+
+#[cfg(feature = "std")]
+impl fmt::Display for ClosedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("popping from a closed and empty queue")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ClosedError {}
+
+/// The error returned by [`SyncPriorityQueue::try_pop`].
+///
+/// [`SyncPriorityQueue::try_pop`]: crate::SyncPriorityQueue::try_pop
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryPopError {
+    /// The queue is empty, but still open.
+    Empty,
+    /// The queue is closed and empty.
+    Closed,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for TryPopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPopError::Empty => f.write_str("popping from an empty queue"),
+            TryPopError::Closed => ClosedError.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TryPopError {}
+
+/// The error returned by [`SyncPriorityQueue::pop_timeout`].
+///
+/// [`SyncPriorityQueue::pop_timeout`]: crate::SyncPriorityQueue::pop_timeout
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PopTimeoutError {
+    /// No element arrived before the timeout.
+    Timeout,
+    /// The queue is closed and empty.
+    Closed,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for PopTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopTimeoutError::Timeout => f.write_str("timed out waiting on an empty queue"),
+            PopTimeoutError::Closed => ClosedError.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for PopTimeoutError {}
@@ -0,0 +1,534 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+use crate::sift;
+use crate::{
+    CapacityError, Comparator, DrainSorted, HeapInvariantError, HeapStorage, IntoIterSorted, Iter,
+    MinOrder, ReserveError, TryInsertError,
+};
+
+/// A priority queue stored as a `D`-ary heap in any [`HeapStorage`].
+///
+/// [`DaryPriorityQueue`] and [`ArrayPriorityQueue`] are this queue over a `Vec` and over inline
+/// storage. Other backends, such as a [`SliceStorage`] borrowed from an arena, go through
+/// [`with_storage`].
+///
+/// # Panics in the comparator
+///
+/// If the comparator panics, the panic propagates to the caller and the queue is left in a
+/// consistent state:
+///
+/// - No element is leaked or dropped twice. The only element lost is the one being returned by
+///   the [`take_front`] call that panicked.
+/// - The queue is marked as [poisoned], since the panic may have interrupted a sift and left the
+///   elements out of heap order. The next operation that needs the heap order rebuilds the heap
+///   first, so a poisoned queue never returns elements out of order. [`peek`] falls back to a
+///   linear scan until then, and [`clear_poison`] rebuilds the heap straight away.
+///
+/// [`DaryPriorityQueue`]: crate::DaryPriorityQueue
+/// [`ArrayPriorityQueue`]: crate::ArrayPriorityQueue
+/// [`SliceStorage`]: crate::SliceStorage
+/// [`with_storage`]: GenericPriorityQueue::with_storage
+/// [`take_front`]: GenericPriorityQueue::take_front
+/// [`peek`]: GenericPriorityQueue::peek
+/// [poisoned]: GenericPriorityQueue::is_poisoned
+/// [`clear_poison`]: GenericPriorityQueue::clear_poison
+#[derive(Clone)]
+pub struct GenericPriorityQueue<T, S, F = MinOrder, const D: usize = 2> {
+    pub(crate) heap: S,
+    pub(crate) cmp: F,
+    /// Set while the comparator is running, so that it stays set if the comparator panics.
+    pub(crate) poisoned: bool,
+    pub(crate) limit: Option<usize>,
+    element: PhantomData<T>,
+}
+
+/// A mutable reference to the front element of a [`GenericPriorityQueue`].
+///
+/// Returned by [`GenericPriorityQueue::peek_mut`]. If the element is modified through this guard,
+/// the queue is restored when the guard is dropped.
+pub struct PeekMut<'a, T, S: HeapStorage<T>, F: Comparator<T>, const D: usize = 2> {
+    queue: &'a mut GenericPriorityQueue<T, S, F, D>,
+    mutated: bool,
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> PeekMut<'_, T, S, F, D> {
+    /// Removes the peeked element from the queue and returns it.
+    pub fn pop(mut this: Self) -> T {
+        // The element is leaving the queue, so there is no need to sift it on drop.
+        this.mutated = false;
+        this.queue.take_front().unwrap()
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Deref for PeekMut<'_, T, S, F, D> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.queue.heap.as_slice()[0]
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> DerefMut for PeekMut<'_, T, S, F, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.mutated = true;
+        &mut self.queue.heap.as_mut_slice()[0]
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Drop for PeekMut<'_, T, S, F, D> {
+    fn drop(&mut self) {
+        if self.mutated {
+            self.queue.sift_down(0);
+        }
+    }
+}
+
+/// Mutable access to all the elements of a [`GenericPriorityQueue`], in arbitrary order.
+///
+/// Returned by [`GenericPriorityQueue::as_mut_slice`]. The heap is rebuilt in O(n) when the guard
+/// is dropped.
+pub struct SliceMut<'a, T, S: HeapStorage<T>, F: Comparator<T>, const D: usize = 2> {
+    queue: &'a mut GenericPriorityQueue<T, S, F, D>,
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Deref for SliceMut<'_, T, S, F, D> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.queue.heap.as_slice()
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> DerefMut for SliceMut<'_, T, S, F, D> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.queue.heap.as_mut_slice()
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Drop for SliceMut<'_, T, S, F, D> {
+    fn drop(&mut self) {
+        self.queue.heapify();
+    }
+}
+
+impl<T: fmt::Debug, S: HeapStorage<T>, F, const D: usize> fmt::Debug
+    for GenericPriorityQueue<T, S, F, D>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.heap.as_slice()).finish()
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> GenericPriorityQueue<T, S, F, D> {
+    /// Creates a queue that keeps its heap in `storage`, starting from the elements already in it.
+    pub fn with_storage(storage: S, cmp: F) -> Self {
+        const { assert!(D >= 2, "a heap needs an arity of at least 2") };
+        let mut queue = Self {
+            heap: storage,
+            cmp,
+            poisoned: false,
+            limit: None,
+            element: PhantomData,
+        };
+        queue.heapify();
+        queue
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        self.repair();
+        let mut front = self.heap.pop().unwrap();
+        if !self.heap.is_empty() {
+            core::mem::swap(&mut front, &mut self.heap.as_mut_slice()[0]);
+            self.sift_down(0);
+        }
+        Some(front)
+    }
+
+    /// Like [`take_front`], but uses Floyd's bottom-up sift, which does roughly half as many
+    /// comparisons. Prefer it when comparisons are expensive.
+    ///
+    /// [`take_front`]: GenericPriorityQueue::take_front
+    pub fn take_front_bottom_up(&mut self) -> Option<T> {
+        self.repair();
+        let mut front = self.heap.pop()?;
+        if !self.heap.is_empty() {
+            core::mem::swap(&mut front, &mut self.heap.as_mut_slice()[0]);
+            self.sift_down_to_bottom(0);
+        }
+        Some(front)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the queue is at its [limit] or its storage is full. A `Vec` aborts instead if
+    /// the allocation fails. See [`try_insert`] for a fallible version.
+    ///
+    /// [limit]: GenericPriorityQueue::set_limit
+    /// [`try_insert`]: GenericPriorityQueue::try_insert
+    pub fn insert(&mut self, element: T) {
+        self.assert_within_limit(1);
+        self.repair();
+        self.heap.push(element);
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    /// Inserts `element`, or gives it back if the queue is at its [limit] or the storage cannot
+    /// make room for it.
+    ///
+    /// [limit]: GenericPriorityQueue::set_limit
+    pub fn try_insert(&mut self, element: T) -> Result<(), TryInsertError<T>> {
+        if let Err(reason) = self.try_reserve(1) {
+            return Err(TryInsertError { element, reason });
+        }
+        self.repair();
+        self.heap.push(element);
+        self.sift_up(self.heap.len() - 1);
+        Ok(())
+    }
+
+    /// Makes room for at least `additional` more elements, without going over the queue's
+    /// [limit].
+    ///
+    /// [limit]: GenericPriorityQueue::set_limit
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        self.check_limit(additional)?;
+        self.heap.try_reserve(additional)
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// The merged queue is ordered by `self`'s comparator. If `self` is the larger queue, the
+    /// elements of `other` are sifted up into it or the heap is rebuilt, whichever is cheaper.
+    /// Otherwise `other`'s storage is reused and the heap is rebuilt, since `other`'s comparator
+    /// may order the elements differently.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the merged queue would go over `self`'s [limit], or if neither storage has room
+    /// for all the elements. Nothing is moved in that case.
+    ///
+    /// [limit]: GenericPriorityQueue::set_limit
+    pub fn append(&mut self, other: &mut Self) {
+        self.assert_within_limit(other.len());
+        let start = if self.heap.len() < other.heap.len()
+            && other.heap.try_reserve(self.heap.len()).is_ok()
+        {
+            mem::swap(&mut self.heap, &mut other.heap);
+            0
+        } else {
+            if let Err(error) = self.heap.try_reserve(other.heap.len()) {
+                panic!("{error}");
+            }
+            self.heap.len()
+        };
+        self.heap.append(&mut other.heap);
+        other.poisoned = false;
+        self.rebuild_tail(start);
+    }
+
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, S, F, D>> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        self.repair();
+        Some(PeekMut {
+            queue: self,
+            mutated: false,
+        })
+    }
+
+    /// Limits how many elements the queue can hold, or removes the limit if `limit` is `None`.
+    ///
+    /// Once the queue holds `limit` elements, [`try_insert`] and [`try_reserve`] return a
+    /// [`CapacityError`], and the methods that cannot fail, such as
+    /// [`insert`](GenericPriorityQueue::insert), panic. Lowering the limit below the current
+    /// length does not remove any elements.
+    ///
+    /// [`try_insert`]: GenericPriorityQueue::try_insert
+    /// [`try_reserve`]: GenericPriorityQueue::try_reserve
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        if self.poisoned {
+            return self.heap.as_slice().iter().reduce(|best, x| {
+                if self.cmp.before(x, best) {
+                    x
+                } else {
+                    best
+                }
+            });
+        }
+        self.heap.as_slice().first()
+    }
+
+    /// Returns the elements in the heap's internal order.
+    pub fn as_slice(&self) -> &[T] {
+        self.heap.as_slice()
+    }
+
+    /// Returns an iterator over the elements in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.into_iter()
+    }
+
+    /// Returns a guard that gives mutable access to the elements, and rebuilds the heap when it is
+    /// dropped.
+    pub fn as_mut_slice(&mut self) -> SliceMut<'_, T, S, F, D> {
+        SliceMut { queue: self }
+    }
+
+    /// Calls `f` on every element and then rebuilds the heap, in O(n) overall.
+    pub fn update_all(&mut self, f: impl FnMut(&mut T)) {
+        // `heapify` clears the poison once the heap is rebuilt.
+        self.poisoned = true;
+        self.heap.as_mut_slice().iter_mut().for_each(f);
+        self.heapify();
+    }
+
+    /// Consumes the queue and returns its storage, with the elements in the heap's internal order.
+    pub fn into_storage(self) -> S {
+        self.heap
+    }
+
+    /// Consumes the queue and returns its storage, with the elements in the order [`take_front`]
+    /// would return them.
+    ///
+    /// The sort is done in place.
+    ///
+    /// [`take_front`]: GenericPriorityQueue::take_front
+    pub fn into_sorted_storage(mut self) -> S {
+        self.repair();
+        let mut end = self.heap.len();
+        while end > 1 {
+            end -= 1;
+            self.heap.swap(0, end);
+            self.sift_down_range(0, end);
+        }
+        // Each front element was moved to the back, so the storage is in reverse priority order.
+        self.heap.as_mut_slice().reverse();
+        self.heap
+    }
+
+    /// Returns an iterator that removes elements in priority order.
+    ///
+    /// The queue is left empty when the iterator is dropped, even if it was not fully consumed.
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, S, F, D> {
+        DrainSorted { queue: self }
+    }
+
+    /// Consumes the queue and returns an iterator over its elements in priority order.
+    pub fn into_iter_sorted(self) -> IntoIterSorted<T, S, F, D> {
+        IntoIterSorted { queue: self }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`.
+    ///
+    /// Runs in O(n): the elements are filtered in one pass and the heap is rebuilt once.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        // The elements are moved out of heap order as they are filtered, so stay poisoned in
+        // case `f` panics.
+        let poisoned = mem::replace(&mut self.poisoned, true);
+        let first_removed = self.filter(|x| f(x));
+        self.poisoned = poisoned;
+        // The elements before the first removed one have not moved, so they are still a heap.
+        self.rebuild_tail(first_removed);
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, letting `f` modify them.
+    ///
+    /// Runs in O(n): the elements are filtered in one pass and the whole heap is rebuilt, since
+    /// any of them may have been modified.
+    pub fn retain_mut(&mut self, f: impl FnMut(&mut T) -> bool) {
+        // `heapify` clears the poison once the heap is rebuilt.
+        self.poisoned = true;
+        self.filter(f);
+        self.heapify();
+    }
+
+    /// Removes and returns the first element, in the heap's internal order, for which `pred`
+    /// returns `true`.
+    ///
+    /// Runs in O(n) to find the element and O(log n) to remove it.
+    pub fn remove_first_matching(&mut self, pred: impl FnMut(&T) -> bool) -> Option<T> {
+        self.repair();
+        let i = self.heap.as_slice().iter().position(pred)?;
+        let last = self.heap.len() - 1;
+        self.heap.swap(i, last);
+        let element = self.heap.pop().unwrap();
+        if i < self.heap.len() {
+            let i = self.sift_up(i);
+            self.sift_down(i);
+        }
+        Some(element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.poisoned = false;
+    }
+
+    /// Returns `true` if the comparator panicked during an earlier operation, which may have left
+    /// the elements out of heap order.
+    ///
+    /// A poisoned queue still behaves correctly, rebuilding the heap when it is next modified.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Rebuilds the heap if the queue is poisoned, clearing the poison.
+    ///
+    /// If the comparator panics again, the queue stays poisoned.
+    pub fn clear_poison(&mut self) {
+        self.repair();
+    }
+
+    /// Checks that every element comes no earlier than its parent, and returns the first pair of
+    /// indices that are out of order.
+    ///
+    /// This always holds unless the queue is [poisoned] or the comparator is not a strict
+    /// ordering. Wrapping the comparator in [`Validated`] helps find out which.
+    ///
+    /// [poisoned]: GenericPriorityQueue::is_poisoned
+    /// [`Validated`]: crate::Validated
+    pub fn check_heap_invariant(&self) -> Result<(), HeapInvariantError> {
+        let heap = self.heap.as_slice();
+        for child in 1..heap.len() {
+            let parent = (child - 1) / D;
+            if self.cmp.before(&heap[child], &heap[parent]) {
+                return Err(HeapInvariantError { parent, child });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `additional` more elements fit within the limit.
+    pub(crate) fn check_limit(&self, additional: usize) -> Result<(), CapacityError> {
+        match self.limit {
+            Some(limit) if additional > limit.saturating_sub(self.heap.len()) => {
+                Err(CapacityError { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Panics if `additional` more elements do not fit within the limit.
+    pub(crate) fn assert_within_limit(&self, additional: usize) {
+        if let Err(error) = self.check_limit(additional) {
+            panic!("{error}");
+        }
+    }
+
+    /// Moves the elements for which `keep` returns `true` to the front, keeping their order, and
+    /// drops the others. Returns the index of the first element removed, or the new length if
+    /// none was.
+    fn filter(&mut self, mut keep: impl FnMut(&mut T) -> bool) -> usize {
+        let len = self.heap.len();
+        let mut kept = 0;
+        let mut first_removed = len;
+        for i in 0..len {
+            if keep(&mut self.heap.as_mut_slice()[i]) {
+                self.heap.swap(kept, i);
+                kept += 1;
+            } else if first_removed == len {
+                first_removed = i;
+            }
+        }
+        while self.heap.len() > kept {
+            self.heap.pop();
+        }
+        first_removed
+    }
+
+    /// Restores the heap after elements have been appended starting at index `start`, either by
+    /// sifting each new element up or by rebuilding the whole heap, whichever is cheaper.
+    pub(crate) fn rebuild_tail(&mut self, start: usize) {
+        if self.poisoned {
+            self.heapify();
+            return;
+        }
+        let len = self.heap.len();
+        let tail_len = len - start;
+        if tail_len == 0 {
+            return;
+        }
+
+        // Sifting up each new element costs about `tail_len * log2(start)` comparisons in the
+        // worst case, while rebuilding costs about `2 * len`.
+        let better_to_rebuild = if start < tail_len {
+            true
+        } else if len <= 2048 {
+            2 * len < tail_len * log2_fast(start)
+        } else {
+            2 * len < tail_len * 11
+        };
+
+        if better_to_rebuild {
+            self.heapify();
+        } else {
+            for i in start..len {
+                self.sift_up(i);
+            }
+        }
+    }
+
+    pub(crate) fn repair(&mut self) {
+        if self.poisoned {
+            self.heapify();
+        }
+    }
+
+    pub(crate) fn heapify(&mut self) {
+        self.poisoned = true;
+        sift::heapify::<T, F, D>(self.heap.as_mut_slice(), &self.cmp);
+        self.poisoned = false;
+    }
+
+    /// Sifts the element at `pos` up and returns its new index.
+    pub(crate) fn sift_up(&mut self, pos: usize) -> usize {
+        self.poisoned = true;
+        let pos = sift::sift_up::<T, F, D>(self.heap.as_mut_slice(), &self.cmp, pos);
+        self.poisoned = false;
+        pos
+    }
+
+    pub(crate) fn sift_down(&mut self, pos: usize) {
+        self.sift_down_range(pos, self.heap.len());
+    }
+
+    /// Sifts the element at `pos` down, treating `heap[..end]` as the whole heap.
+    pub(crate) fn sift_down_range(&mut self, pos: usize, end: usize) {
+        self.poisoned = true;
+        sift::sift_down::<T, F, D>(&mut self.heap.as_mut_slice()[..end], &self.cmp, pos);
+        self.poisoned = false;
+    }
+
+    fn sift_down_to_bottom(&mut self, pos: usize) {
+        self.poisoned = true;
+        sift::sift_down_to_bottom::<T, F, D>(self.heap.as_mut_slice(), &self.cmp, pos);
+        self.poisoned = false;
+    }
+}
+
+fn log2_fast(x: usize) -> usize {
+    (usize::BITS - x.leading_zeros() - 1) as usize
+}
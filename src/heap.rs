@@ -0,0 +1,107 @@
+//! Binary heap operations on slices the caller owns, in the spirit of C++'s `<algorithm>`.
+//!
+//! A slice is a heap under `cmp` if no element comes before its parent, so `heap[0]` is the
+//! element a [`PriorityQueue`] with the same comparator would take first.
+//!
+//! [`PriorityQueue`]: crate::PriorityQueue
+
+use crate::sift;
+use crate::Comparator;
+
+/// Rearranges `heap` into a heap in O(n).
+pub fn make_heap<T, F: Comparator<T>>(heap: &mut [T], cmp: F) {
+    sift::heapify::<T, F, 2>(heap, &cmp);
+}
+
+/// Adds the last element of `heap` to the heap formed by the elements before it.
+pub fn push_heap<T, F: Comparator<T>>(heap: &mut [T], cmp: F) {
+    if let Some(last) = heap.len().checked_sub(1) {
+        sift::sift_up::<T, F, 2>(heap, &cmp, last);
+    }
+}
+
+/// Moves the front element of `heap` to the end, and makes the elements before it a heap again.
+pub fn pop_heap<T, F: Comparator<T>>(heap: &mut [T], cmp: F) {
+    if heap.len() > 1 {
+        let last = heap.len() - 1;
+        heap.swap(0, last);
+        sift::sift_down::<T, F, 2>(&mut heap[..last], &cmp, 0);
+    }
+}
+
+/// Sorts a heap in place, into the order its elements would be taken from a queue.
+///
+/// The elements are popped to the back one by one, which leaves them in reverse order, and then
+/// reversed.
+pub fn sort_heap<T, F: Comparator<T>>(heap: &mut [T], cmp: F) {
+    for end in (1..heap.len()).rev() {
+        heap.swap(0, end);
+        sift::sift_down::<T, F, 2>(&mut heap[..end], &cmp, 0);
+    }
+    heap.reverse();
+}
+
+/// Returns `true` if `heap` is a heap.
+pub fn is_heap<T, F: Comparator<T>>(heap: &[T], cmp: F) -> bool {
+    is_heap_until(heap, cmp) == heap.len()
+}
+
+/// Returns the length of the longest prefix of `heap` that is a heap.
+pub fn is_heap_until<T, F: Comparator<T>>(heap: &[T], cmp: F) -> usize {
+    (1..heap.len())
+        .find(|&child| cmp.before(&heap[child], &heap[(child - 1) / 2]))
+        .unwrap_or(heap.len())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{is_heap, is_heap_until, make_heap, pop_heap, push_heap, sort_heap};
+    use crate::{MaxOrder, MinOrder};
+
+    #[test]
+    fn make_and_sort() {
+        let mut v = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3];
+        assert!(!is_heap(&v, MinOrder));
+        make_heap(&mut v, MinOrder);
+        assert!(is_heap(&v, MinOrder));
+        assert_eq!(v[0], 1);
+        sort_heap(&mut v, MinOrder);
+        assert_eq!(v, [1, 1, 2, 3, 3, 4, 5, 5, 6, 9]);
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut v = Vec::new();
+        for x in [5, 8, 2, 7] {
+            v.push(x);
+            push_heap(&mut v, MaxOrder);
+            assert!(is_heap(&v, MaxOrder));
+        }
+        let mut taken = Vec::new();
+        while !v.is_empty() {
+            pop_heap(&mut v, MaxOrder);
+            taken.push(v.pop().unwrap());
+            assert!(is_heap(&v, MaxOrder));
+        }
+        assert_eq!(taken, [8, 7, 5, 2]);
+    }
+
+    #[test]
+    fn heap_until() {
+        let by_len = |a: &&str, b: &&str| a.len() < b.len();
+        assert_eq!(is_heap_until(&["a", "bb", "ccc", "d"], by_len), 3);
+        assert_eq!(is_heap_until(&["a", "bb", "ccc"], by_len), 3);
+        assert_eq!(is_heap_until::<i32, _>(&[], MinOrder), 0);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut empty: [i32; 0] = [];
+        push_heap(&mut empty, MinOrder);
+        pop_heap(&mut empty, MinOrder);
+        sort_heap(&mut empty, MinOrder);
+        let mut one = [7];
+        pop_heap(&mut one, MinOrder);
+        assert_eq!(one, [7]);
+    }
+}
@@ -0,0 +1,88 @@
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+/// An element temporarily moved out of a slice, leaving a hole at its old index.
+///
+/// Sifting moves the hole rather than swapping elements, so each level costs one move instead of
+/// three, and the element is written only once, at its final index. If a comparison panics, the
+/// element is written back into the hole when the `Hole` is dropped, so the slice never loses or
+/// duplicates an element.
+///
+/// `moved` is called with every element written to a new index, including the one in the hole
+/// when it is filled, so that a queue can keep an index of positions up to date.
+pub(crate) struct Hole<'a, T, M: FnMut(&T, usize)> {
+    data: &'a mut [T],
+    element: ManuallyDrop<T>,
+    pos: usize,
+    moved: M,
+}
+
+impl<'a, T, M: FnMut(&T, usize)> Hole<'a, T, M> {
+    /// Creates a hole at index `pos`.
+    ///
+    /// # Safety
+    ///
+    /// `pos` must be within `data`.
+    pub(crate) unsafe fn new(data: &'a mut [T], pos: usize, moved: M) -> Self {
+        debug_assert!(pos < data.len());
+        // SAFETY: `pos` is within the slice, and the value read is written back on drop.
+        let element = unsafe { ptr::read(data.get_unchecked(pos)) };
+        Hole {
+            data,
+            element: ManuallyDrop::new(element),
+            pos,
+            moved,
+        }
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the element that was moved out.
+    pub(crate) fn element(&self) -> &T {
+        &self.element
+    }
+
+    /// Returns the element at `index`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be within the slice and must not be the hole.
+    pub(crate) unsafe fn get(&self, index: usize) -> &T {
+        debug_assert!(index != self.pos);
+        debug_assert!(index < self.data.len());
+        // SAFETY: the caller guarantees `index` is within the slice and initialized.
+        unsafe { self.data.get_unchecked(index) }
+    }
+
+    /// Moves the element at `index` into the hole, leaving the hole at `index`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be within the slice and must not be the hole.
+    pub(crate) unsafe fn move_to(&mut self, index: usize) {
+        debug_assert!(index != self.pos);
+        debug_assert!(index < self.data.len());
+        // SAFETY: both indices are within the slice and distinct, and `pos` is the hole.
+        unsafe {
+            let ptr = self.data.as_mut_ptr();
+            ptr::copy_nonoverlapping(ptr.add(index), ptr.add(self.pos), 1);
+            (self.moved)(&*ptr.add(self.pos), self.pos);
+        }
+        self.pos = index;
+    }
+}
+
+impl<T, M: FnMut(&T, usize)> Drop for Hole<'_, T, M> {
+    fn drop(&mut self) {
+        let pos = self.pos;
+        // SAFETY: `pos` is the hole, so writing the element there makes the slice whole again.
+        let element = unsafe {
+            let slot = self.data.get_unchecked_mut(pos);
+            ptr::copy_nonoverlapping(&*self.element, slot, 1);
+            &*slot
+        };
+        (self.moved)(element, pos);
+    }
+}
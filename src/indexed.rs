@@ -0,0 +1,183 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use std::collections::HashMap;
+
+use crate::comparator::ByPriority;
+use crate::{AddressablePriorityQueue, Comparator, Handle, MinOrder};
+
+/// A priority queue of keys, each with a priority that can be looked up and changed by key.
+///
+/// Each key is in the queue at most once. Pushing a key that is already queued changes its
+/// priority instead of adding it again. The key is cloned once when it is first pushed, so that
+/// it can be kept both in the heap and in the index.
+pub struct IndexedPriorityQueue<K, P, F = MinOrder> {
+    queue: AddressablePriorityQueue<(K, P), ByPriority<F>>,
+    handles: HashMap<K, Handle>,
+}
+
+impl<K: Hash + Eq + Clone, P: PartialOrd> IndexedPriorityQueue<K, P> {
+    pub fn new() -> Self {
+        Self::with_comparator(MinOrder)
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: PartialOrd> Default for IndexedPriorityQueue<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, P: fmt::Debug, F> fmt::Debug for IndexedPriorityQueue<K, P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.queue.fmt(f)
+    }
+}
+
+impl<K: Hash + Eq + Clone, P, F: Fn(&P, &P) -> bool> IndexedPriorityQueue<K, P, F> {
+    pub fn with_ordering(ordering: F) -> Self {
+        Self::with_comparator(ordering)
+    }
+}
+
+impl<K: Hash + Eq + Clone, P, F: Comparator<P>> IndexedPriorityQueue<K, P, F> {
+    pub fn with_comparator(cmp: F) -> Self {
+        Self {
+            queue: AddressablePriorityQueue::with_comparator(ByPriority(cmp)),
+            handles: HashMap::new(),
+        }
+    }
+
+    /// Queues `key` with `priority`. If `key` is already queued, its priority is changed instead
+    /// and the old priority is returned.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if let Some(&handle) = self.handles.get(&key) {
+            return self.replace(handle, priority);
+        }
+        let handle = self.queue.insert((key.clone(), priority));
+        self.handles.insert(key, handle);
+        None
+    }
+
+    /// Changes the priority of a queued key and returns the old priority, or returns `None`
+    /// without queueing anything if `key` is not in the queue.
+    pub fn change_priority<Q>(&mut self, key: &Q, priority: P) -> Option<P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.handles.get(key)?;
+        self.replace(handle, priority)
+    }
+
+    /// Removes `key` from the queue and returns its priority.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.handles.remove(key)?;
+        self.queue.remove(handle).map(|(_, priority)| priority)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.handles.contains_key(key)
+    }
+
+    pub fn get_priority<Q>(&self, key: &Q) -> Option<&P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.handles.get(key)?;
+        self.queue.get(handle).map(|(_, priority)| priority)
+    }
+
+    /// Removes the key with the front priority and returns it along with its priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let (key, priority) = self.queue.take_front()?;
+        self.handles.remove(&key);
+        Some((key, priority))
+    }
+
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.queue.peek().map(|(key, priority)| (key, priority))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.handles.clear();
+    }
+
+    fn replace(&mut self, handle: Handle, priority: P) -> Option<P> {
+        let mut old = None;
+        self.queue.update(handle, |entry| {
+            old = Some(core::mem::replace(&mut entry.1, priority))
+        });
+        old
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::IndexedPriorityQueue;
+
+    #[test]
+    fn push_and_pop() {
+        let mut queue = IndexedPriorityQueue::new();
+        assert_eq!(queue.push("b", 2), None);
+        assert_eq!(queue.push("a", 3), None);
+        assert_eq!(queue.push("c", 1), None);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some((&"c", &1)));
+        assert_eq!(queue.pop(), Some(("c", 1)));
+        assert_eq!(queue.pop(), Some(("b", 2)));
+        assert_eq!(queue.pop(), Some(("a", 3)));
+        assert_eq!(queue.pop(), None);
+        assert!(!queue.contains_key("a"));
+    }
+
+    #[test]
+    fn upsert() {
+        let mut queue = IndexedPriorityQueue::new();
+        queue.push(1, 10);
+        queue.push(2, 20);
+        assert_eq!(queue.push(2, 5), Some(20));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.get_priority(&2), Some(&5));
+        assert_eq!(queue.pop(), Some((2, 5)));
+    }
+
+    #[test]
+    fn change_priority_and_remove() {
+        let mut queue = IndexedPriorityQueue::with_ordering(|a: &u32, b: &u32| a > b);
+        for (id, priority) in [
+            (String::from("x"), 1),
+            (String::from("y"), 2),
+            (String::from("z"), 3),
+        ] {
+            queue.push(id, priority);
+        }
+        assert_eq!(queue.change_priority("x", 10), Some(1));
+        assert_eq!(queue.change_priority("w", 10), None);
+        assert!(!queue.contains_key("w"));
+        assert_eq!(queue.remove("z"), Some(3));
+        assert_eq!(queue.remove("z"), None);
+        assert_eq!(queue.pop(), Some((String::from("x"), 10)));
+        assert_eq!(queue.pop(), Some((String::from("y"), 2)));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use core::iter::FusedIterator;
+use core::slice;
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+use crate::{Comparator, GenericPriorityQueue, HeapStorage};
+#[cfg(feature = "alloc")]
+use crate::{DaryPriorityQueue, MinOrder};
+
+/// An owning iterator over the elements of a [`DaryPriorityQueue`], in arbitrary order.
+#[cfg(feature = "alloc")]
+pub struct IntoIter<T> {
+    pub(crate) iter: vec::IntoIter<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T> FusedIterator for IntoIter<T> {}
+
+/// A borrowing iterator over the elements of a [`GenericPriorityQueue`], in arbitrary order.
+pub struct Iter<'a, T> {
+    iter: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+/// A draining iterator over the elements of a [`GenericPriorityQueue`], in priority order.
+///
+/// Returned by [`GenericPriorityQueue::drain_sorted`].
+pub struct DrainSorted<'a, T, S: HeapStorage<T>, F: Comparator<T>, const D: usize = 2> {
+    pub(crate) queue: &'a mut GenericPriorityQueue<T, S, F, D>,
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Iterator
+    for DrainSorted<'_, T, S, F, D>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.queue.take_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.queue.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> ExactSizeIterator
+    for DrainSorted<'_, T, S, F, D>
+{
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> FusedIterator
+    for DrainSorted<'_, T, S, F, D>
+{
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Drop for DrainSorted<'_, T, S, F, D> {
+    fn drop(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// An owning iterator over the elements of a [`GenericPriorityQueue`], in priority order.
+///
+/// Returned by [`GenericPriorityQueue::into_iter_sorted`].
+pub struct IntoIterSorted<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize = 2> {
+    pub(crate) queue: GenericPriorityQueue<T, S, F, D>,
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Iterator
+    for IntoIterSorted<T, S, F, D>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.queue.take_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.queue.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> ExactSizeIterator
+    for IntoIterSorted<T, S, F, D>
+{
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> FusedIterator
+    for IntoIterSorted<T, S, F, D>
+{
+}
+
+#[cfg(feature = "alloc")]
+impl<T, F: Comparator<T>, const D: usize> IntoIterator for DaryPriorityQueue<T, F, D> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            iter: self.heap.into_iter(),
+        }
+    }
+}
+
+impl<'a, T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> IntoIterator
+    for &'a GenericPriorityQueue<T, S, F, D>
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        Iter {
+            iter: self.heap.as_slice().iter(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: PartialOrd, const D: usize> FromIterator<T> for DaryPriorityQueue<T, MinOrder, D> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<T, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Extend<T>
+    for GenericPriorityQueue<T, S, F, D>
+{
+    /// # Panics
+    ///
+    /// Panics if the queue would go over its [limit](GenericPriorityQueue::set_limit), or if its
+    /// storage fills up. The elements that fit are kept.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.heap.len();
+        let room = self.limit.map(|limit| limit.saturating_sub(start));
+        let mut iter = iter.into_iter();
+        // The new elements are out of order until the tail is rebuilt, so stay poisoned in case
+        // the iterator panics.
+        let poisoned = core::mem::replace(&mut self.poisoned, true);
+        for element in iter.by_ref().take(room.unwrap_or(usize::MAX)) {
+            self.heap.push(element);
+        }
+        // Pull one more element, to find out whether there were too many.
+        let overflow = room.is_some() && iter.next().is_some();
+        self.poisoned = poisoned;
+        self.rebuild_tail(start);
+        if overflow {
+            self.assert_within_limit(1);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, S: HeapStorage<T>, F: Comparator<T>, const D: usize> Extend<&'a T>
+    for GenericPriorityQueue<T, S, F, D>
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
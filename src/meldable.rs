@@ -0,0 +1,286 @@
+use core::mem;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{Comparator, MinOrder};
+
+struct Node<T> {
+    element: T,
+    /// The first of this node's children.
+    child: Option<Box<Node<T>>>,
+    /// The next child of this node's parent.
+    sibling: Option<Box<Node<T>>>,
+}
+
+/// A priority queue that can be merged with another in O(1).
+///
+/// This is a pairing heap: [`insert`] and [`append`] run in O(1) and [`take_front`] in amortized
+/// O(log n). It is slower than [`PriorityQueue`] for plain inserts and removals, so prefer it only
+/// when queues are merged often.
+///
+/// If the comparator panics, the panic propagates and no element other than the one being taken
+/// is lost. The trees that were being linked are kept apart and the queue is [poisoned]: the next
+/// operation links them back together, and [`peek`] scans their roots until then.
+///
+/// [`insert`]: MeldablePriorityQueue::insert
+/// [`append`]: MeldablePriorityQueue::append
+/// [`take_front`]: MeldablePriorityQueue::take_front
+/// [`peek`]: MeldablePriorityQueue::peek
+/// [`PriorityQueue`]: crate::PriorityQueue
+/// [poisoned]: MeldablePriorityQueue::is_poisoned
+pub struct MeldablePriorityQueue<T, F = MinOrder> {
+    /// Heap-ordered trees whose roots have not been compared yet. Outside of an operation this
+    /// holds at most one tree unless the comparator panicked.
+    trees: VecDeque<Box<Node<T>>>,
+    len: usize,
+    cmp: F,
+}
+
+impl<T: PartialOrd> MeldablePriorityQueue<T> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T: PartialOrd> Default for MeldablePriorityQueue<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> MeldablePriorityQueue<T, F> {
+    pub fn with_ordering(data: Vec<T>, ordering: F) -> Self {
+        Self::with_comparator(data, ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> MeldablePriorityQueue<T, F> {
+    pub fn with_comparator(data: Vec<T>, cmp: F) -> Self {
+        let mut queue = Self {
+            trees: VecDeque::new(),
+            len: 0,
+            cmp,
+        };
+        for element in data {
+            queue.insert(element);
+        }
+        queue
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        self.merge_trees();
+        let root = self.trees.pop_front()?;
+        let Node {
+            element, mut child, ..
+        } = *root;
+        self.len -= 1;
+        while let Some(mut node) = child {
+            child = node.sibling.take();
+            self.trees.push_back(node);
+        }
+        self.merge_trees();
+        Some(element)
+    }
+
+    pub fn insert(&mut self, element: T) {
+        self.trees.push_back(Box::new(Node {
+            element,
+            child: None,
+            sibling: None,
+        }));
+        self.len += 1;
+        self.merge_trees();
+    }
+
+    /// Moves all the elements of `other` into `self` in O(1), leaving `other` empty.
+    ///
+    /// The trees of both queues are kept as they are, so `other` must order elements the same way
+    /// as `self`. It is a logic error for the two comparators to disagree, such as two closures of
+    /// the same type that capture different state. The merged queue would then return elements
+    /// out of order.
+    pub fn append(&mut self, other: &mut Self) {
+        self.trees.append(&mut other.trees);
+        self.len += mem::take(&mut other.len);
+        self.merge_trees();
+    }
+
+    /// Returns the front element, scanning the roots of the separate trees if the queue is
+    /// poisoned.
+    pub fn peek(&self) -> Option<&T> {
+        self.trees.iter().map(|tree| &tree.element).reduce(|a, b| {
+            if self.cmp.before(b, a) {
+                b
+            } else {
+                a
+            }
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        drop_nodes(mem::take(&mut self.trees));
+        self.len = 0;
+    }
+
+    /// Returns `true` if the comparator panicked during an earlier operation, leaving the
+    /// elements in several trees that have not been linked together yet.
+    ///
+    /// A poisoned queue still behaves correctly, linking the trees when it is next modified.
+    pub fn is_poisoned(&self) -> bool {
+        self.trees.len() > 1
+    }
+
+    /// Links the trees back together if the queue is poisoned, clearing the poison.
+    ///
+    /// If the comparator panics again, the queue stays poisoned.
+    pub fn clear_poison(&mut self) {
+        self.merge_trees();
+    }
+
+    /// Merges `trees` into one tree: first links them in pairs from front to back, then links
+    /// the pairs together from back to front.
+    ///
+    /// Two roots are compared while both trees are still in `trees`, so a panicking comparator
+    /// leaves every tree in place.
+    fn merge_trees(&mut self) {
+        if self.trees.len() < 2 {
+            return;
+        }
+        for _ in 0..self.trees.len() / 2 {
+            let (a, b) = (&self.trees[0], &self.trees[1]);
+            let b_first = self.cmp.before(&b.element, &a.element);
+            let a = self.trees.pop_front().unwrap();
+            let b = self.trees.pop_front().unwrap();
+            self.trees.push_back(link(a, b, b_first));
+        }
+        if self.trees.len() % 2 == 1 {
+            // An odd tree out stays last, after the pairs.
+            self.trees.rotate_left(1);
+        }
+        while let [.., a, b] = self.trees.make_contiguous() {
+            let b_first = self.cmp.before(&b.element, &a.element);
+            let b = self.trees.pop_back().unwrap();
+            let a = self.trees.pop_back().unwrap();
+            self.trees.push_back(link(a, b, b_first));
+        }
+    }
+}
+
+/// Makes the later of two roots the first child of the earlier one, where `b_first` tells
+/// whether `b` comes first.
+fn link<T>(a: Box<Node<T>>, b: Box<Node<T>>, b_first: bool) -> Box<Node<T>> {
+    let (mut parent, mut child) = if b_first { (b, a) } else { (a, b) };
+    child.sibling = parent.child.take();
+    parent.child = Some(child);
+    parent
+}
+
+impl<T, F> Drop for MeldablePriorityQueue<T, F> {
+    fn drop(&mut self) {
+        drop_nodes(mem::take(&mut self.trees));
+    }
+}
+
+/// Drops trees without recursing, since sibling and child lists can be as long as the queue.
+fn drop_nodes<T>(trees: VecDeque<Box<Node<T>>>) {
+    let mut stack: Vec<_> = trees.into();
+    while let Some(mut node) = stack.pop() {
+        stack.extend(node.child.take());
+        stack.extend(node.sibling.take());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{MaxOrder, MeldablePriorityQueue};
+
+    #[test]
+    fn take_front() {
+        let mut queue = MeldablePriorityQueue::new(vec![3, 2, 6, 5, 1, 4]);
+        queue.insert(0);
+        assert_eq!(queue.len(), 7);
+        assert_eq!(queue.peek(), Some(&0));
+        let order: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(order, [0, 1, 2, 3, 4, 5, 6]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn append() {
+        let mut a = MeldablePriorityQueue::with_comparator(vec![1, 5, 9], MaxOrder);
+        let mut b = MeldablePriorityQueue::with_comparator(vec![2, 8, 7], MaxOrder);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert_eq!(b.take_front(), None);
+        assert_eq!(a.len(), 6);
+        let order: Vec<_> = std::iter::from_fn(|| a.take_front()).collect();
+        assert_eq!(order, [9, 8, 7, 5, 2, 1]);
+    }
+
+    #[test]
+    fn large_queue_drops_without_overflow() {
+        let mut queue = MeldablePriorityQueue::new((0..200_000).collect());
+        queue.insert(-1);
+        assert_eq!(queue.take_front(), Some(-1));
+        assert_eq!(queue.take_front(), Some(0));
+        drop(queue);
+        let mut queue = MeldablePriorityQueue::new((0..200_000).rev().collect());
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn comparator_panic_keeps_every_element() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        for panic_at in 1..40 {
+            let comparisons = Cell::new(0);
+            let armed = Cell::new(false);
+            let data: Vec<_> = (0..20).rev().map(|i| (i, token.clone())).collect();
+            let mut queue = MeldablePriorityQueue::with_ordering(data, |a: &(i32, Rc<()>), b| {
+                comparisons.set(comparisons.get() + 1);
+                if armed.get() && comparisons.get() == panic_at {
+                    panic!("comparator panicked");
+                }
+                a.0 < b.0
+            });
+            queue.take_front();
+            armed.set(true);
+            comparisons.set(0);
+            let mut taken = 0;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                queue.insert((-1, token.clone()));
+                queue.take_front();
+                taken += 1;
+                queue.take_front();
+                taken += 1;
+            }));
+            assert_eq!(result.is_err(), taken < 2);
+            // A panic during `take_front` drops the element that was being taken.
+            let len = queue.len();
+            assert!(len == 20 - taken || result.is_err() && len == 19 - taken);
+            let front = queue.peek().map(|(i, _)| *i);
+            armed.set(false);
+            let remaining: Vec<_> = std::iter::from_fn(|| queue.take_front())
+                .map(|(i, _)| i)
+                .collect();
+            assert!(!queue.is_poisoned());
+            assert_eq!(remaining.len(), len);
+            assert_eq!(remaining.first().copied(), front);
+            assert!(remaining.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(Rc::strong_count(&token), 1);
+        }
+    }
+}
@@ -0,0 +1,915 @@
+use core::cmp::Ordering;
+
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+
+use crate::{
+    ByKey, CachedKeyPriorityQueue, Comparator, GenericPriorityQueue, IntoIter, MaxOrder, MinOrder,
+};
+
+/// A priority queue stored as a binary heap.
+pub type PriorityQueue<T, F = MinOrder> = DaryPriorityQueue<T, F, 2>;
+
+/// A priority queue stored as a `D`-ary heap, where every node has up to `D` children.
+///
+/// A higher arity makes the heap shallower, so [`insert`] does fewer comparisons and the children
+/// of a node share cache lines, at the cost of more comparisons per level in [`take_front`].
+/// [`PriorityQueue`] is the `D = 2` case.
+///
+/// The heap is kept in a `Vec`; see [`GenericPriorityQueue`] for the operations shared with other
+/// storage, and for how the queue behaves when the comparator panics.
+///
+/// [`insert`]: DaryPriorityQueue::insert
+/// [`take_front`]: GenericPriorityQueue::take_front
+pub type DaryPriorityQueue<T, F = MinOrder, const D: usize = 2> =
+    GenericPriorityQueue<T, Vec<T>, F, D>;
+
+impl<T: PartialOrd, const D: usize> DaryPriorityQueue<T, MinOrder, D> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T: Ord, const D: usize> DaryPriorityQueue<T, MinOrder, D> {
+    /// Creates a queue whose front is the smallest element.
+    pub fn min_heap(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T: Ord, const D: usize> DaryPriorityQueue<T, MaxOrder, D> {
+    /// Creates a queue whose front is the largest element.
+    pub fn max_heap(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MaxOrder)
+    }
+}
+
+impl<T, G, const D: usize> DaryPriorityQueue<T, ByKey<G>, D> {
+    /// Creates a queue whose front is the element with the smallest key.
+    ///
+    /// The key is recomputed on every comparison; see [`by_cached_key`] if that is expensive.
+    ///
+    /// [`by_cached_key`]: PriorityQueue::by_cached_key
+    pub fn by_key<K>(data: Vec<T>, key: G) -> Self
+    where
+        K: Ord,
+        G: Fn(&T) -> K,
+    {
+        Self::with_comparator(data, ByKey(key))
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    /// Creates a queue ordered by a comparison function such as [`Ord::cmp`]. Elements that compare
+    /// as [`Ordering::Less`] come out first.
+    pub fn with_cmp<C>(data: Vec<T>, cmp: C) -> PriorityQueue<T, impl Fn(&T, &T) -> bool>
+    where
+        C: Fn(&T, &T) -> Ordering,
+    {
+        PriorityQueue::with_ordering(data, move |a, b| cmp(a, b) == Ordering::Less)
+    }
+
+    /// Creates a queue whose front is the element with the smallest key, computing each key only
+    /// once.
+    pub fn by_cached_key<K, G>(data: Vec<T>, key: G) -> CachedKeyPriorityQueue<T, K, G>
+    where
+        K: Ord,
+        G: Fn(&T) -> K,
+    {
+        CachedKeyPriorityQueue::new(data, key)
+    }
+}
+
+impl<T: PartialOrd, const D: usize> Default for DaryPriorityQueue<T, MinOrder, D> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool, const D: usize> DaryPriorityQueue<T, F, D> {
+    pub fn with_ordering(data: Vec<T>, ordering: F) -> Self {
+        Self::with_comparator(data, ordering)
+    }
+
+    /// Creates an empty queue with room for at least `capacity` elements, or returns an error if
+    /// the allocation fails.
+    pub fn try_with_ordering(capacity: usize, ordering: F) -> Result<Self, TryReserveError> {
+        let mut data = Vec::new();
+        data.try_reserve(capacity)?;
+        Ok(Self::with_ordering(data, ordering))
+    }
+}
+
+impl<T, F: Comparator<T>, const D: usize> DaryPriorityQueue<T, F, D> {
+    /// Creates a queue ordered by any [`Comparator`], such as [`MaxOrder`] or [`Reverse`].
+    ///
+    /// [`Reverse`]: crate::Reverse
+    pub fn with_comparator(data: Vec<T>, cmp: F) -> Self {
+        Self::with_storage(data, cmp)
+    }
+
+    /// Consumes the queue and returns its elements in the order [`take_front`] would return them.
+    ///
+    /// The sort is done in place, reusing the queue's buffer.
+    ///
+    /// [`take_front`]: GenericPriorityQueue::take_front
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.into_sorted_storage()
+    }
+
+    /// Consumes the queue and returns its elements in the heap's internal order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.heap.reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.heap.shrink_to_fit();
+    }
+
+    /// Removes the elements for which `pred` returns `true` and returns them, in arbitrary order.
+    ///
+    /// Runs in O(n): the elements are filtered in one pass and the heap is rebuilt once.
+    pub fn extract_if(&mut self, mut pred: impl FnMut(&T) -> bool) -> IntoIter<T> {
+        let mut first_removed = self.heap.len();
+        let mut i = 0;
+        let poisoned = core::mem::replace(&mut self.poisoned, true);
+        let removed: Vec<T> = self
+            .heap
+            .extract_if(.., |x| {
+                let remove = pred(x);
+                if remove && i < first_removed {
+                    first_removed = i;
+                }
+                i += 1;
+                remove
+            })
+            .collect();
+        self.poisoned = poisoned;
+        self.rebuild_tail(first_removed);
+        IntoIter {
+            iter: removed.into_iter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ByKey, DaryPriorityQueue, HeapInvariantError, MaxOrder, MinOrder, PeekMut, PriorityQueue,
+        Reverse, Validated,
+    };
+    #[test]
+    fn new() {
+        let mut queue = PriorityQueue::new(vec![3, 2, 6, 5, 1, 4]);
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), Some(2));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), Some(4));
+        assert_eq!(queue.take_front(), Some(5));
+        assert_eq!(queue.take_front(), Some(6));
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn insert() {
+        let mut queue = PriorityQueue::new(vec![1, 5, 9]);
+        queue.insert(8);
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), Some(5));
+        assert_eq!(queue.take_front(), Some(8));
+        assert_eq!(queue.take_front(), Some(9));
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn insert_new_front() {
+        let mut queue = PriorityQueue::new(Vec::new());
+        for i in (0..8).rev() {
+            queue.insert(i);
+            assert_eq!(queue.peek(), Some(&i));
+        }
+        assert_eq!(queue.into_sorted_vec(), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn peek() {
+        let mut queue = PriorityQueue::new(vec![4, 2, 7]);
+        assert_eq!(queue.peek(), Some(&2));
+        assert_eq!(queue.len(), 3);
+        queue.insert(1);
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.peek(), Some(&2));
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut queue = PriorityQueue::new(vec![1, 5, 3]);
+        *queue.peek_mut().unwrap() = 4;
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), Some(4));
+        assert_eq!(queue.take_front(), Some(5));
+        assert!(queue.peek_mut().is_none());
+    }
+
+    #[test]
+    fn peek_mut_pop() {
+        let mut queue = PriorityQueue::new(vec![2, 1, 3]);
+        let front = queue.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(front), 1);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.take_front(), Some(2));
+    }
+
+    #[test]
+    fn capacity_and_clear() {
+        let mut queue = PriorityQueue::new(Vec::new());
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+        queue.reserve(10);
+        assert!(queue.capacity() >= 10);
+        queue.insert(3);
+        queue.insert(1);
+        assert!(!queue.is_empty());
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.take_front(), None);
+        queue.shrink_to_fit();
+        assert_eq!(queue.capacity(), 0);
+    }
+
+    #[test]
+    fn collect() {
+        let mut queue: PriorityQueue<_, _> = vec![3, 1, 2].into_iter().collect();
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), Some(2));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn extend() {
+        // Small batch: elements are sifted up one at a time.
+        let mut queue = PriorityQueue::new((10..100).collect());
+        queue.extend([5, 50, 1]);
+        // Large batch: the heap is rebuilt.
+        queue.extend((0..1000).rev());
+        queue.extend(&[7, 3]);
+        let mut expected: Vec<_> = (10..100).chain([5, 50, 1, 7, 3]).chain(0..1000).collect();
+        expected.sort();
+        let mut actual = Vec::new();
+        while let Some(x) = queue.take_front() {
+            actual.push(x);
+        }
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn append() {
+        let mut a = PriorityQueue::new(vec![1, 5, 9]);
+        let mut b = PriorityQueue::new((2..100).collect());
+        a.append(&mut b);
+        assert!(b.is_empty());
+        let mut expected: Vec<_> = [1, 5, 9].into_iter().chain(2..100).collect();
+        expected.sort();
+        assert_eq!(a.into_sorted_vec(), expected);
+
+        let mut a = PriorityQueue::new((0..1000).rev().collect());
+        let mut b = PriorityQueue::new(vec![500, -1]);
+        a.append(&mut b);
+        assert_eq!(a.len(), 1002);
+        assert_eq!(a.take_front(), Some(-1));
+    }
+
+    #[test]
+    fn append_with_different_ordering() {
+        let ordering = |k: i32| move |a: &i32, b: &i32| a * k < b * k;
+        let mut a = PriorityQueue::with_ordering(vec![3], ordering(1));
+        let mut b = PriorityQueue::with_ordering(vec![1, 2, 4, 5], ordering(-1));
+        a.append(&mut b);
+        assert_eq!(a.check_heap_invariant(), Ok(()));
+        assert_eq!(a.into_sorted_vec(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn into_iter() {
+        let queue = PriorityQueue::new(vec![3, 1, 2]);
+        let mut borrowed: Vec<_> = (&queue).into_iter().copied().collect();
+        borrowed.sort();
+        assert_eq!(borrowed, [1, 2, 3]);
+        let mut owned: Vec<_> = queue.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, [1, 2, 3]);
+    }
+
+    #[test]
+    fn into_sorted_vec() {
+        let queue = PriorityQueue::new(vec![3, 2, 6, 5, 1, 4]);
+        assert_eq!(queue.into_sorted_vec(), [1, 2, 3, 4, 5, 6]);
+        let queue = PriorityQueue::with_ordering(vec![3, 2, 6, 5, 1, 4], |a, b| a > b);
+        assert_eq!(queue.into_sorted_vec(), [6, 5, 4, 3, 2, 1]);
+        assert!(PriorityQueue::<i32, _>::default()
+            .into_sorted_vec()
+            .is_empty());
+    }
+
+    #[test]
+    fn drain_sorted() {
+        let mut queue = PriorityQueue::new(vec![3, 2, 6, 5, 1, 4]);
+        let mut drain = queue.drain_sorted();
+        assert_eq!(drain.len(), 6);
+        assert_eq!(drain.next(), Some(1));
+        assert_eq!(drain.next(), Some(2));
+        assert_eq!(drain.size_hint(), (4, Some(4)));
+        drop(drain);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn into_iter_sorted() {
+        let queue = PriorityQueue::new(vec![3, 2, 6, 5, 1, 4]);
+        let iter = queue.into_iter_sorted();
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn clone_debug_default() {
+        let mut queue = PriorityQueue::default();
+        queue.insert(2);
+        queue.insert(1);
+        assert_eq!(format!("{queue:?}"), "[1, 2]");
+        let mut cloned = queue.clone();
+        assert_eq!(cloned.take_front(), Some(1));
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn custom_comparator_ascending() {
+        let mut queue = PriorityQueue::with_ordering(vec![3, 2, 6, 5, 1, 4], |a, b| a < b);
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), Some(2));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), Some(4));
+        assert_eq!(queue.take_front(), Some(5));
+        assert_eq!(queue.take_front(), Some(6));
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn custom_comparator_descending() {
+        let mut queue = PriorityQueue::with_ordering(vec![3, 2, 6, 5, 1, 4], |a, b| a > b);
+        assert_eq!(queue.take_front(), Some(6));
+        assert_eq!(queue.take_front(), Some(5));
+        assert_eq!(queue.take_front(), Some(4));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), Some(2));
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), None);
+    }
+
+    #[test]
+    fn min_and_max_heap() {
+        let min = PriorityQueue::min_heap(vec![3, 1, 2]);
+        assert_eq!(min.into_sorted_vec(), [1, 2, 3]);
+        let max = PriorityQueue::max_heap(vec![3, 1, 2]);
+        assert_eq!(max.into_sorted_vec(), [3, 2, 1]);
+    }
+
+    #[test]
+    fn with_cmp() {
+        let queue = PriorityQueue::with_cmp(vec![2.5, -1.0, 10.0, 0.0], f64::total_cmp);
+        assert_eq!(queue.into_sorted_vec(), [-1.0, 0.0, 2.5, 10.0]);
+
+        // Longest first, then alphabetical.
+        let words = vec!["bb", "a", "ccc", "aa", "c"];
+        let queue = PriorityQueue::with_cmp(words, |a: &&str, b: &&str| {
+            b.len().cmp(&a.len()).then_with(|| a.cmp(b))
+        });
+        assert_eq!(queue.into_sorted_vec(), ["ccc", "aa", "bb", "a", "c"]);
+    }
+
+    #[test]
+    fn by_key() {
+        let queue =
+            PriorityQueue::by_key(vec![(1, 'a'), (-3, 'b'), (2, 'c')], |x: &(i32, char)| {
+                x.0.abs()
+            });
+        assert_eq!(queue.into_sorted_vec(), [(1, 'a'), (2, 'c'), (-3, 'b')]);
+    }
+
+    #[test]
+    fn by_cached_key() {
+        use std::cell::Cell;
+
+        let calls = Cell::new(0);
+        let mut queue = PriorityQueue::by_cached_key(vec!["ccc", "a", "bb"], |s: &&str| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+        assert_eq!(calls.get(), 3);
+        queue.insert("dddd");
+        queue.insert("");
+        assert_eq!(calls.get(), 5);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.peek(), Some(&""));
+        assert_eq!(queue.take_front(), Some(""));
+        assert_eq!(queue.take_front(), Some("a"));
+        assert_eq!(queue.take_front(), Some("bb"));
+        assert_eq!(queue.take_front(), Some("ccc"));
+        assert_eq!(queue.take_front(), Some("dddd"));
+        assert!(queue.is_empty());
+        assert_eq!(calls.get(), 5);
+    }
+
+    #[test]
+    fn named_comparators() {
+        let queue = PriorityQueue::with_comparator(vec![3, 1, 2], Reverse(MinOrder));
+        assert_eq!(queue.into_sorted_vec(), [3, 2, 1]);
+        let queue = PriorityQueue::with_comparator(vec![3, 1, 2], Reverse(MaxOrder));
+        assert_eq!(queue.into_sorted_vec(), [1, 2, 3]);
+        let queue = PriorityQueue::with_comparator(vec![-3, 1, 2], ByKey(|x: &i32| x.abs()));
+        assert_eq!(queue.into_sorted_vec(), [1, 2, -3]);
+        assert_eq!(
+            core::mem::size_of::<PriorityQueue<u8>>(),
+            core::mem::size_of::<PriorityQueue<u8, Reverse<MaxOrder>>>()
+        );
+        assert!(
+            core::mem::size_of::<PriorityQueue<u8>>()
+                < core::mem::size_of::<PriorityQueue<u8, fn(&u8, &u8) -> bool>>()
+        );
+    }
+
+    #[test]
+    fn arity() {
+        fn check<const D: usize>() {
+            let data: Vec<u32> = (0..500).map(|i| i * 7919 % 503).collect();
+            let mut expected = data.clone();
+            expected.sort();
+            let mut queue = DaryPriorityQueue::<_, _, D>::new(data[..250].to_vec());
+            for &x in &data[250..] {
+                queue.insert(x);
+            }
+            assert_eq!(queue.peek(), Some(&0));
+            let mut clone = queue.clone();
+            let popped: Vec<_> = std::iter::from_fn(|| clone.take_front()).collect();
+            assert_eq!(popped, expected);
+            assert_eq!(queue.into_sorted_vec(), expected);
+        }
+        check::<2>();
+        check::<3>();
+        check::<4>();
+        check::<8>();
+        check::<16>();
+    }
+
+    #[test]
+    fn take_front_bottom_up() {
+        use std::cell::Cell;
+
+        let data: Vec<u32> = (0..1000).map(|i| i * 7919 % 1009).collect();
+        let mut expected = data.clone();
+        expected.sort();
+
+        let comparisons = Cell::new(0);
+        let counting = |a: &u32, b: &u32| {
+            comparisons.set(comparisons.get() + 1);
+            a < b
+        };
+
+        let mut queue = PriorityQueue::with_ordering(data.clone(), &counting);
+        comparisons.set(0);
+        let top_down: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        let top_down_comparisons = comparisons.get();
+
+        let mut queue = PriorityQueue::with_ordering(data, &counting);
+        comparisons.set(0);
+        let bottom_up: Vec<_> = std::iter::from_fn(|| queue.take_front_bottom_up()).collect();
+        let bottom_up_comparisons = comparisons.get();
+
+        assert_eq!(top_down, expected);
+        assert_eq!(bottom_up, expected);
+        assert!(bottom_up_comparisons < top_down_comparisons * 3 / 4);
+    }
+
+    #[test]
+    fn comparator_panic_keeps_every_element() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        for panic_at in 1..40 {
+            let comparisons = Cell::new(0);
+            let armed = Cell::new(false);
+            let data: Vec<_> = (0..20).rev().map(|i| (i, token.clone())).collect();
+            let mut queue = PriorityQueue::with_ordering(data, |a: &(i32, Rc<()>), b| {
+                comparisons.set(comparisons.get() + 1);
+                if armed.get() && comparisons.get() == panic_at {
+                    panic!("comparator panicked");
+                }
+                a.0 < b.0
+            });
+            armed.set(true);
+            comparisons.set(0);
+            let mut taken = 0;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                queue.insert((-1, token.clone()));
+                queue.take_front();
+                taken += 1;
+                queue.take_front_bottom_up();
+                taken += 1;
+            }));
+            assert_eq!(result.is_err(), taken < 2);
+            // A panic during `take_front` drops the element that was being taken.
+            let len = queue.len();
+            assert!(len == 21 - taken || result.is_err() && len == 20 - taken);
+            let mut remaining: Vec<_> = queue.into_iter().map(|(i, _)| i).collect();
+            remaining.sort();
+            remaining.dedup();
+            assert_eq!(remaining.len(), len);
+            assert_eq!(Rc::strong_count(&token), 1);
+        }
+    }
+
+    #[test]
+    fn comparator_panic_at_every_comparison() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        type Op = fn(&mut PriorityQueue<u32, &dyn Fn(&u32, &u32) -> bool>);
+        let ops: [(&str, Op); 6] = [
+            ("insert", |q| q.insert(7)),
+            ("take_front", |q| {
+                q.take_front();
+            }),
+            ("take_front_bottom_up", |q| {
+                q.take_front_bottom_up();
+            }),
+            ("extend", |q| q.extend([40, 3, 17, 0, 25])),
+            ("extend large", |q| q.extend(0..100)),
+            ("peek_mut", |q| *q.peek_mut().unwrap() = 50),
+        ];
+
+        let comparisons = Cell::new(0);
+        let panic_at = Cell::new(0);
+        let cmp = |a: &u32, b: &u32| {
+            comparisons.set(comparisons.get() + 1);
+            if comparisons.get() == panic_at.get() {
+                panic!("comparator panicked");
+            }
+            a < b
+        };
+
+        for (name, op) in ops {
+            for i in 1.. {
+                let data: Vec<u32> = (0..30).map(|x| x * 17 % 31).collect();
+                panic_at.set(0);
+                let mut queue =
+                    PriorityQueue::with_ordering(data, &cmp as &dyn Fn(&u32, &u32) -> bool);
+                let mut expected = queue.clone();
+                op(&mut expected);
+                let expected = expected.into_sorted_vec();
+
+                comparisons.set(0);
+                panic_at.set(i);
+                let panicked = catch_unwind(AssertUnwindSafe(|| op(&mut queue))).is_err();
+                panic_at.set(0);
+                if !panicked {
+                    assert!(!queue.is_poisoned());
+                    break;
+                }
+                assert!(queue.is_poisoned(), "{name} at comparison {i}");
+
+                let front = *queue.peek().unwrap();
+                let actual = queue.into_sorted_vec();
+                assert_eq!(front, actual[0], "{name} at comparison {i}");
+                // A panicking `take_front` loses the element being taken, but every operation
+                // otherwise has the same effect as if it had not panicked.
+                assert_eq!(actual, expected, "{name} at comparison {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn extend_with_panicking_iterator() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut queue = PriorityQueue::new((0..10).collect());
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.extend(
+                [-1, -2]
+                    .into_iter()
+                    .chain(std::iter::from_fn(|| -> Option<i32> {
+                        panic!("iterator panicked")
+                    })),
+            );
+        }));
+        assert!(result.is_err());
+        assert!(queue.is_poisoned());
+        assert_eq!(queue.peek(), Some(&-2));
+        assert_eq!(queue.take_front(), Some(-2));
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        assert_eq!(queue.into_sorted_vec(), [-1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn clear_poison() {
+        let mut queue = PriorityQueue::with_ordering(vec![5, 4, 3, 2, 1], |a: &i32, b: &i32| {
+            assert!(*a != 0 && *b != 0, "cannot compare 0");
+            a < b
+        });
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            *queue.peek_mut().unwrap() = 0;
+        }));
+        assert!(result.is_err());
+        assert!(queue.is_poisoned());
+        *queue.heap.iter_mut().find(|x| **x == 0).unwrap() = 6;
+        queue.clear_poison();
+        assert!(!queue.is_poisoned());
+        assert_eq!(queue.into_sorted_vec(), [2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn check_heap_invariant() {
+        let mut queue = PriorityQueue::new((0..10).rev().collect());
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        queue.heap[4] = -1;
+        let error = queue.check_heap_invariant().unwrap_err();
+        assert_eq!(
+            error,
+            HeapInvariantError {
+                parent: 1,
+                child: 4
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "heap element 4 comes before its parent at index 1"
+        );
+
+        let queue = DaryPriorityQueue::<_, _, 4>::new((0..100).rev().collect());
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+    }
+
+    #[test]
+    fn validated_comparator() {
+        use std::panic::catch_unwind;
+
+        let queue = PriorityQueue::with_comparator(vec![3, 1, 2], Validated(MinOrder));
+        assert_eq!(queue.into_sorted_vec(), [1, 2, 3]);
+
+        let reflexive = catch_unwind(|| {
+            PriorityQueue::with_comparator(vec![3, 1, 2], Validated(|a: &i32, b: &i32| a <= b))
+        });
+        assert!(reflexive.is_err());
+
+        let symmetric = catch_unwind(|| {
+            PriorityQueue::with_comparator(vec![3, 1, 2], Validated(|a: &i32, b: &i32| a != b))
+        });
+        assert!(symmetric.is_err());
+    }
+
+    #[test]
+    fn retain() {
+        let mut queue = PriorityQueue::new((0..100).collect());
+        queue.retain(|x| x % 3 != 0);
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        let expected: Vec<_> = (0..100).filter(|x| x % 3 != 0).collect();
+        assert_eq!(queue.clone().into_sorted_vec(), expected);
+
+        queue.retain(|&x| x > 90);
+        assert_eq!(queue.into_sorted_vec(), [91, 92, 94, 95, 97, 98]);
+    }
+
+    #[test]
+    fn retain_mut() {
+        let mut queue = PriorityQueue::new((0..20).collect());
+        queue.retain_mut(|x| {
+            *x = 100 - *x;
+            *x % 2 == 0
+        });
+        let expected: Vec<_> = (81..=100).filter(|x| x % 2 == 0).collect();
+        assert_eq!(queue.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut queue = PriorityQueue::new(vec![(1, "a"), (2, "b"), (1, "c"), (3, "a"), (0, "a")]);
+        let mut cancelled: Vec<_> = queue.extract_if(|&(_, user)| user == "a").collect();
+        cancelled.sort();
+        assert_eq!(cancelled, [(0, "a"), (1, "a"), (3, "a")]);
+        assert_eq!(queue.into_sorted_vec(), [(1, "c"), (2, "b")]);
+    }
+
+    #[test]
+    fn filter_with_panicking_predicate() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let check = |queue: PriorityQueue<i32>| {
+            assert!(queue.is_poisoned());
+            let mut expected = queue.as_slice().to_vec();
+            expected.sort();
+            assert_eq!(queue.peek(), expected.first());
+            assert_eq!(queue.into_sorted_vec(), expected);
+        };
+        let panic_after = |n| {
+            let mut calls = 0;
+            move || {
+                calls += 1;
+                assert!(calls < n, "predicate panicked");
+            }
+        };
+
+        let mut queue = PriorityQueue::new((0..20).collect());
+        let mut tick = panic_after(10);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.retain(|x| {
+                tick();
+                x % 3 != 0
+            })
+        }));
+        assert!(result.is_err());
+        check(queue);
+
+        let mut queue = PriorityQueue::new((0..20).collect());
+        let mut tick = panic_after(10);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.retain_mut(|x| {
+                tick();
+                *x = 100 - *x;
+                true
+            })
+        }));
+        assert!(result.is_err());
+        check(queue);
+
+        let mut queue = PriorityQueue::new((0..20).collect());
+        let mut tick = panic_after(10);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.extract_if(|x| {
+                tick();
+                x % 3 == 0
+            })
+        }));
+        assert!(result.is_err());
+        check(queue);
+    }
+
+    #[test]
+    fn remove_first_matching() {
+        let mut queue = PriorityQueue::new((0..50).rev().collect());
+        assert_eq!(queue.remove_first_matching(|&x| x == 20), Some(20));
+        assert_eq!(queue.remove_first_matching(|&x| x == 20), None);
+        assert_eq!(queue.remove_first_matching(|&x| x == 0), Some(0));
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        let expected: Vec<_> = (1..50).filter(|&x| x != 20).collect();
+        assert_eq!(queue.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn iter_and_slices() {
+        let queue = PriorityQueue::new(vec![4, 2, 7, 1]);
+        assert_eq!(queue.iter().len(), 4);
+        assert_eq!(queue.as_slice()[0], 1);
+        let mut items: Vec<_> = queue.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, [1, 2, 4, 7]);
+        let mut vec = queue.into_vec();
+        vec.sort();
+        assert_eq!(vec, [1, 2, 4, 7]);
+    }
+
+    #[test]
+    fn update_all() {
+        let mut queue = PriorityQueue::new(vec![(10.0, 'a'), (20.0, 'b'), (30.0, 'c')]);
+        queue.update_all(|(score, _)| *score *= 0.9);
+        assert_eq!(queue.peek(), Some(&(9.0, 'a')));
+
+        // Reverse the order through the slice guard.
+        {
+            let mut slice = queue.as_mut_slice();
+            for (score, _) in slice.iter_mut() {
+                *score = -*score;
+            }
+        }
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        assert_eq!(queue.take_front(), Some((-27.0, 'c')));
+    }
+
+    #[test]
+    fn update_all_with_panicking_closure() {
+        let mut queue = PriorityQueue::new((0..10).collect());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            queue.update_all(|x| {
+                assert!(*x != 5, "closure panicked");
+                *x = -*x;
+            })
+        }));
+        assert!(result.is_err());
+        // Only the elements before 5 were changed.
+        assert!(queue.is_poisoned());
+        assert_eq!(queue.peek(), Some(&-4));
+        queue.clear_poison();
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        assert_eq!(queue.take_front(), Some(-4));
+    }
+
+    #[test]
+    fn non_partial_ord() {
+        #[derive(Debug)]
+        enum NonPartialOrd {
+            One,
+            Two,
+            Three,
+        }
+        use NonPartialOrd::*;
+
+        // Order by: a < b
+        let mut queue = PriorityQueue::with_ordering(vec![Two, One, Three], |a, b| match (a, b) {
+            (One, Two) | (One, Three) | (Two, Three) => true,
+            (_, _) => false,
+        });
+
+        match queue.take_front() {
+            Some(One) => { /* good! */ }
+            x => panic!("{x:?} != Some(One)"),
+        }
+        match queue.take_front() {
+            Some(Two) => { /* good! */ }
+            x => panic!("{x:?} != Some(Two)"),
+        }
+        match queue.take_front() {
+            Some(Three) => { /* good! */ }
+            x => panic!("{x:?} != Some(Three)"),
+        }
+        match queue.take_front() {
+            None => { /* good! */ }
+            x => panic!("{x:?} != None"),
+        }
+    }
+
+    #[test]
+    fn limit() {
+        use crate::{CapacityError, ReserveError};
+
+        let mut queue = PriorityQueue::new(vec![4, 2]);
+        queue.set_limit(Some(3));
+        assert_eq!(queue.limit(), Some(3));
+        assert_eq!(queue.try_insert(3), Ok(()));
+        let error = queue.try_insert(1).unwrap_err();
+        assert_eq!(
+            error.reason(),
+            &ReserveError::Capacity(CapacityError { limit: 3 })
+        );
+        assert_eq!(error.into_element(), 1);
+        assert_eq!(
+            queue.try_reserve(1),
+            Err(ReserveError::Capacity(CapacityError { limit: 3 }))
+        );
+        assert_eq!(queue.try_reserve(0), Ok(()));
+
+        queue.set_limit(None);
+        queue.insert(1);
+        assert_eq!(queue.into_sorted_vec(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn limit_panics() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut queue = PriorityQueue::new(vec![5]);
+        queue.set_limit(Some(3));
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.extend([3, 9, 1, 7]))).is_err());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.insert(0))).is_err());
+        let mut other = PriorityQueue::new(vec![0]);
+        assert!(catch_unwind(AssertUnwindSafe(|| queue.append(&mut other))).is_err());
+        assert_eq!(queue.into_sorted_vec(), [3, 5, 9]);
+    }
+
+    #[test]
+    fn allocation_failure() {
+        use crate::ReserveError;
+
+        let mut queue = PriorityQueue::<u64, _>::try_with_ordering(4, |a, b| a < b).unwrap();
+        assert!(queue.capacity() >= 4);
+        assert!(matches!(
+            queue.try_reserve(usize::MAX),
+            Err(ReserveError::Alloc(_))
+        ));
+        assert!(PriorityQueue::<u64, _>::try_with_ordering(usize::MAX, |a, b| a < b).is_err());
+        assert_eq!(queue.try_insert(1), Ok(()));
+    }
+}
@@ -0,0 +1,69 @@
+use alloc::vec::Vec;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Comparator, DaryPriorityQueue};
+
+/// Serializes the elements as a sequence, in the heap's internal order.
+///
+/// The comparator is not serialized. The named comparators such as [`MinOrder`] carry no state,
+/// so their type is all that is needed to rebuild the queue.
+///
+/// [`MinOrder`]: crate::MinOrder
+impl<T: Serialize, F, const D: usize> Serialize for DaryPriorityQueue<T, F, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.heap.serialize(serializer)
+    }
+}
+
+/// Deserializes a sequence of elements and rebuilds the heap with `F::default()`.
+///
+/// The heap is always rebuilt in O(n), so input that is not in heap order, whether it was edited
+/// by hand or serialized with a different ordering, is repaired rather than trusted.
+impl<'de, T, F, const D: usize> Deserialize<'de> for DaryPriorityQueue<T, F, D>
+where
+    T: Deserialize<'de>,
+    F: Comparator<T> + Default,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let heap = Vec::deserialize(deserializer)?;
+        Ok(Self::with_comparator(heap, F::default()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DaryPriorityQueue, MaxOrder, PriorityQueue, Reverse};
+
+    #[test]
+    fn round_trip() {
+        let queue = PriorityQueue::new(vec![3, 1, 4, 1, 5, 9, 2, 6]);
+        let json = serde_json::to_string(&queue).unwrap();
+        let restored: PriorityQueue<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.as_slice(), queue.as_slice());
+        assert_eq!(restored.into_sorted_vec(), [1, 1, 2, 3, 4, 5, 6, 9]);
+    }
+
+    #[test]
+    fn named_comparators() {
+        let queue = PriorityQueue::max_heap(vec![3, 1, 4]);
+        let json = serde_json::to_string(&queue).unwrap();
+        let restored: PriorityQueue<i32, MaxOrder> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.into_sorted_vec(), [4, 3, 1]);
+
+        let restored: DaryPriorityQueue<i32, Reverse<MaxOrder>, 4> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.into_sorted_vec(), [1, 3, 4]);
+
+        assert_eq!(serde_json::to_string(&Reverse(MaxOrder)).unwrap(), "null");
+    }
+
+    #[test]
+    fn repairs_invalid_heap() {
+        let restored: PriorityQueue<i32> = serde_json::from_str("[5, 4, 3, 2, 1]").unwrap();
+        assert_eq!(restored.check_heap_invariant(), Ok(()));
+        assert_eq!(restored.into_sorted_vec(), [1, 2, 3, 4, 5]);
+
+        let error = serde_json::from_str::<PriorityQueue<i32>>("[1, \"two\"]");
+        assert!(error.is_err());
+    }
+}
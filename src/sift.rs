@@ -0,0 +1,142 @@
+//! Heap operations on slices, shared by every queue that stores its elements as a `D`-ary heap
+//! in an array.
+//!
+//! A slice is a heap if no element comes before its parent, where the parent of index `i` is
+//! `(i - 1) / D`.
+//!
+//! The `_tracked` variants call `moved(element, index)` for every element they write to a new
+//! index, for queues that keep track of where each element is.
+
+use crate::hole::Hole;
+use crate::Comparator;
+
+/// Sifts the element at `pos` up and returns its new index.
+pub(crate) fn sift_up<T, F: Comparator<T>, const D: usize>(
+    heap: &mut [T],
+    cmp: &F,
+    pos: usize,
+) -> usize {
+    sift_up_tracked::<T, F, D>(heap, cmp, pos, |_, _| {})
+}
+
+pub(crate) fn sift_up_tracked<T, F: Comparator<T>, const D: usize>(
+    heap: &mut [T],
+    cmp: &F,
+    pos: usize,
+    moved: impl FnMut(&T, usize),
+) -> usize {
+    assert!(pos < heap.len());
+    // SAFETY: `pos` was checked to be within the heap.
+    let mut hole = unsafe { Hole::new(heap, pos, moved) };
+    while hole.pos() != 0 {
+        let parent = (hole.pos() - 1) / D;
+        // SAFETY: `parent` is less than the hole's index, so it is in bounds and not the hole.
+        if !cmp.before(hole.element(), unsafe { hole.get(parent) }) {
+            break;
+        }
+        // SAFETY: as above.
+        unsafe { hole.move_to(parent) };
+    }
+    hole.pos()
+}
+
+/// Sifts the element at `pos` down.
+pub(crate) fn sift_down<T, F: Comparator<T>, const D: usize>(heap: &mut [T], cmp: &F, pos: usize) {
+    sift_down_tracked::<T, F, D>(heap, cmp, pos, |_, _| {});
+}
+
+pub(crate) fn sift_down_tracked<T, F: Comparator<T>, const D: usize>(
+    heap: &mut [T],
+    cmp: &F,
+    pos: usize,
+    moved: impl FnMut(&T, usize),
+) {
+    assert!(pos < heap.len());
+    let end = heap.len();
+    // SAFETY: `pos` was checked to be within the heap.
+    let mut hole = unsafe { Hole::new(heap, pos, moved) };
+    loop {
+        let first_child = hole.pos() * D + 1;
+        if first_child >= end {
+            break;
+        }
+        // SAFETY: every child index compared is greater than the hole's index and less than
+        // `end`, so it is in bounds and not the hole.
+        unsafe {
+            let best = front_child::<T, F, D>(&hole, cmp, first_child, end);
+            if !cmp.before(hole.get(best), hole.element()) {
+                break;
+            }
+            hole.move_to(best);
+        }
+    }
+}
+
+/// Moves the element at `pos` all the way down to a leaf, always following the front child,
+/// and then sifts it back up.
+///
+/// The element being sifted is usually one from the bottom of the heap, which will end up near
+/// the bottom again. Not comparing it against the children on the way down saves one comparison
+/// per level, and the sift back up is usually short.
+pub(crate) fn sift_down_to_bottom<T, F: Comparator<T>, const D: usize>(
+    heap: &mut [T],
+    cmp: &F,
+    pos: usize,
+) {
+    assert!(pos < heap.len());
+    let end = heap.len();
+    // SAFETY: `pos` was checked to be within the heap.
+    let mut hole = unsafe { Hole::new(heap, pos, |_, _| {}) };
+    loop {
+        let first_child = hole.pos() * D + 1;
+        if first_child >= end {
+            break;
+        }
+        // SAFETY: every child index is greater than the hole's index and less than `end`.
+        unsafe {
+            let best = front_child::<T, F, D>(&hole, cmp, first_child, end);
+            hole.move_to(best);
+        }
+    }
+    let leaf = hole.pos();
+    drop(hole);
+    sift_up::<T, F, D>(heap, cmp, leaf);
+}
+
+/// Turns an arbitrary slice into a heap in O(n).
+pub(crate) fn heapify<T, F: Comparator<T>, const D: usize>(heap: &mut [T], cmp: &F) {
+    heapify_tracked::<T, F, D>(heap, cmp, |_, _| {});
+}
+
+pub(crate) fn heapify_tracked<T, F: Comparator<T>, const D: usize>(
+    heap: &mut [T],
+    cmp: &F,
+    mut moved: impl FnMut(&T, usize),
+) {
+    for i in (0..heap.len()).rev() {
+        sift_down_tracked::<T, F, D>(heap, cmp, i, &mut moved);
+    }
+}
+
+/// Returns the index of the child that comes first among the children starting at
+/// `first_child`.
+///
+/// # Safety
+///
+/// `first_child` must be less than `end`, and the children up to `end` must be in bounds and not
+/// the hole.
+unsafe fn front_child<T, F: Comparator<T>, const D: usize>(
+    hole: &Hole<'_, T, impl FnMut(&T, usize)>,
+    cmp: &F,
+    first_child: usize,
+    end: usize,
+) -> usize {
+    let mut best = first_child;
+    for child in first_child + 1..(first_child + D).min(end) {
+        // SAFETY: guaranteed by the caller.
+        if cmp.before(unsafe { hole.get(child) }, unsafe { hole.get(best) }) {
+            best = child;
+        }
+    }
+    best
+}
@@ -0,0 +1,166 @@
+use alloc::vec::Vec;
+
+use crate::{Comparator, MinOrder, PriorityQueue};
+
+struct Stamped<T> {
+    seq: u64,
+    value: T,
+}
+
+/// Breaks ties in `F` by insertion order.
+struct StableOrder<F>(F);
+
+impl<T, F: Comparator<T>> Comparator<Stamped<T>> for StableOrder<F> {
+    fn before(&self, a: &Stamped<T>, b: &Stamped<T>) -> bool {
+        if self.0.before(&a.value, &b.value) {
+            true
+        } else if self.0.before(&b.value, &a.value) {
+            false
+        } else {
+            a.seq < b.seq
+        }
+    }
+}
+
+/// A priority queue that takes elements of equal priority in the order they were inserted.
+///
+/// Each element is tagged with a sequence number when it is inserted. Elements of the initial
+/// `data` are treated as inserted in the order they appear in the vector.
+pub struct StablePriorityQueue<T, F = MinOrder> {
+    queue: PriorityQueue<Stamped<T>, StableOrder<F>>,
+    next_seq: u64,
+}
+
+impl<T: PartialOrd> StablePriorityQueue<T> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> StablePriorityQueue<T, F> {
+    pub fn with_ordering(data: Vec<T>, ordering: F) -> Self {
+        Self::with_comparator(data, ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> StablePriorityQueue<T, F> {
+    pub fn with_comparator(data: Vec<T>, cmp: F) -> Self {
+        let heap: Vec<_> = data
+            .into_iter()
+            .zip(0..)
+            .map(|(value, seq)| Stamped { seq, value })
+            .collect();
+        let next_seq = heap.len() as u64;
+        Self {
+            queue: PriorityQueue::with_comparator(heap, StableOrder(cmp)),
+            next_seq,
+        }
+    }
+
+    pub fn take_front(&mut self) -> Option<T> {
+        self.queue.take_front().map(|stamped| stamped.value)
+    }
+
+    pub fn insert(&mut self, element: T) {
+        if self.next_seq == u64::MAX {
+            self.renumber();
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.insert(Stamped {
+            seq,
+            value: element,
+        });
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.queue.peek().map(|stamped| &stamped.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.next_seq = 0;
+    }
+
+    /// Compacts the sequence numbers of the queued elements to `0..len`, keeping their relative
+    /// order, so that numbering can continue after `u64::MAX` insertions.
+    fn renumber(&mut self) {
+        let heap = &mut self.queue.heap;
+        heap.sort_unstable_by_key(|stamped| stamped.seq);
+        for (stamped, seq) in heap.iter_mut().zip(0..) {
+            stamped.seq = seq;
+        }
+        self.next_seq = heap.len() as u64;
+        self.queue.heapify();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::StablePriorityQueue;
+
+    #[test]
+    fn equal_keys_in_insertion_order() {
+        let mut queue =
+            StablePriorityQueue::with_ordering(Vec::new(), |a: &(u32, u32), b| a.0 < b.0);
+        for i in 0..1000 {
+            queue.insert((i % 3, i));
+        }
+        for key in 0..3 {
+            let mut last = None;
+            for _ in 0..(1000 + 2 - key) / 3 {
+                let (k, i) = queue.take_front().unwrap();
+                assert_eq!(k, key);
+                assert!(last < Some(i));
+                last = Some(i);
+            }
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn initial_data_in_vector_order() {
+        let data = vec![(1, 'a'), (0, 'b'), (1, 'c'), (0, 'd'), (1, 'e')];
+        let mut queue = StablePriorityQueue::with_ordering(data, |a: &(u8, char), b| a.0 < b.0);
+        queue.insert((0, 'f'));
+        let order: Vec<_> = std::iter::from_fn(|| queue.take_front())
+            .map(|x| x.1)
+            .collect();
+        assert_eq!(order, ['b', 'd', 'f', 'a', 'c', 'e']);
+    }
+
+    #[test]
+    fn sequence_overflow() {
+        let mut queue =
+            StablePriorityQueue::with_ordering(Vec::new(), |a: &(u8, u32), b| a.0 < b.0);
+        queue.next_seq = u64::MAX - 5;
+        for i in 0..20 {
+            queue.insert((0, i));
+        }
+        assert_eq!(queue.next_seq, 20);
+        for i in 0..20 {
+            assert_eq!(queue.take_front(), Some((0, i)));
+        }
+    }
+
+    #[test]
+    fn orders_by_priority_first() {
+        let mut queue = StablePriorityQueue::new(vec![3, 1, 2]);
+        queue.insert(0);
+        assert_eq!(queue.peek(), Some(&0));
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.take_front(), Some(0));
+        assert_eq!(queue.take_front(), Some(1));
+        assert_eq!(queue.take_front(), Some(2));
+        assert_eq!(queue.take_front(), Some(3));
+        assert_eq!(queue.take_front(), None);
+    }
+}
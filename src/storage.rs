@@ -0,0 +1,337 @@
+use core::mem::MaybeUninit;
+use core::{ptr, slice};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{CapacityError, ReserveError};
+
+/// A growable array that a [`GenericPriorityQueue`] keeps its heap in.
+///
+/// The heap operations only ever see the elements through [`as_mut_slice`], so a backend only
+/// has to be able to add and remove elements at the end. Implementations are provided for
+/// [`Vec`], the inline [`ArrayStorage`] and the borrowed [`SliceStorage`].
+///
+/// [`GenericPriorityQueue`]: crate::GenericPriorityQueue
+/// [`as_mut_slice`]: HeapStorage::as_mut_slice
+pub trait HeapStorage<T> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Makes room for at least `additional` more elements, or returns why it cannot.
+    fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError>;
+
+    /// Appends `element`.
+    ///
+    /// A backend with a fixed capacity may panic if it is full. The queue's fallible methods call
+    /// [`try_reserve`] first, and the others panic.
+    ///
+    /// [`try_reserve`]: HeapStorage::try_reserve
+    fn push(&mut self, element: T);
+
+    /// Removes the last element and returns it.
+    fn pop(&mut self) -> Option<T>;
+
+    /// Moves all the elements of `other` to the end of `self`, in any order, leaving `other`
+    /// empty.
+    ///
+    /// The queue only calls this after [`try_reserve`] has made room.
+    ///
+    /// [`try_reserve`]: HeapStorage::try_reserve
+    fn append(&mut self, other: &mut Self)
+    where
+        Self: Sized,
+    {
+        while let Some(element) = other.pop() {
+            self.push(element);
+        }
+    }
+
+    /// Returns the elements. The slice must be exactly [`len`](HeapStorage::len) long.
+    fn as_slice(&self) -> &[T];
+
+    /// Returns the elements. The slice must be exactly [`len`](HeapStorage::len) long.
+    fn as_mut_slice(&mut self) -> &mut [T];
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.as_mut_slice().swap(a, b);
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> HeapStorage<T> for Vec<T> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        Ok(Vec::try_reserve(self, additional)?)
+    }
+
+    fn push(&mut self, element: T) {
+        Vec::push(self, element);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+
+    fn append(&mut self, other: &mut Self) {
+        Vec::append(self, other);
+    }
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+}
+
+/// Storage for up to `N` elements, kept inline without allocating.
+pub struct ArrayStorage<T, const N: usize> {
+    /// The first `len` elements are initialized.
+    data: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayStorage<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            data: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+}
+
+impl<T, const N: usize> Default for ArrayStorage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayStorage<T, N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        for x in self.as_slice() {
+            clone.push(x.clone());
+        }
+        clone
+    }
+}
+
+impl<T, const N: usize> HeapStorage<T> for ArrayStorage<T, N> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        if additional > N - self.len {
+            return Err(CapacityError { limit: N }.into());
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, element: T) {
+        assert!(self.len < N, "array storage is full");
+        self.data[self.len].write(element);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the element was initialized, and is no longer counted by `len`.
+        Some(unsafe { self.data[self.len].assume_init_read() })
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts(self.data.as_ptr().cast(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr().cast(), self.len) }
+    }
+
+    fn clear(&mut self) {
+        let elements: *mut [T] = self.as_mut_slice();
+        // Forget the elements before dropping them, in case a destructor panics.
+        self.len = 0;
+        // SAFETY: the elements were initialized and are no longer reachable through `self`.
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayStorage<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Storage in a buffer borrowed from the caller, such as a slab carved out of an arena.
+///
+/// The buffer starts out empty and its length is the capacity. The elements still in it are
+/// dropped along with the storage.
+///
+/// The buffer is made of `MaybeUninit<T>` because the queue moves elements out of it, which
+/// would leave holes in a borrowed `&mut [T]` once the borrow ends. To keep a heap in a slice of
+/// initialized elements, use [`make_heap`] and the other slice functions instead.
+///
+/// [`make_heap`]: crate::make_heap
+pub struct SliceStorage<'a, T> {
+    /// The first `len` elements are initialized.
+    buf: &'a mut [MaybeUninit<T>],
+    len: usize,
+}
+
+impl<'a, T> SliceStorage<'a, T> {
+    pub fn new(buf: &'a mut [MaybeUninit<T>]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl<T> HeapStorage<T> for SliceStorage<'_, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        if additional > self.buf.len() - self.len {
+            return Err(CapacityError {
+                limit: self.buf.len(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, element: T) {
+        assert!(self.len < self.buf.len(), "slice storage is full");
+        self.buf[self.len].write(element);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the element was initialized, and is no longer counted by `len`.
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first `len` elements are initialized.
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+
+    fn clear(&mut self) {
+        let elements: *mut [T] = self.as_mut_slice();
+        // Forget the elements before dropping them, in case a destructor panics.
+        self.len = 0;
+        // SAFETY: the elements were initialized and are no longer reachable through `self`.
+        unsafe { ptr::drop_in_place(elements) };
+    }
+}
+
+impl<T> Drop for SliceStorage<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::MaybeUninit;
+    use std::rc::Rc;
+
+    use crate::{
+        CapacityError, GenericPriorityQueue, HeapStorage, MaxOrder, MinOrder, ReserveError,
+        SliceStorage,
+    };
+
+    #[test]
+    fn slice_storage() {
+        let mut buf = [const { MaybeUninit::uninit() }; 4];
+        let mut queue =
+            GenericPriorityQueue::<_, _, _>::with_storage(SliceStorage::new(&mut buf), MaxOrder);
+        for x in [2, 7, 1, 8] {
+            assert_eq!(queue.try_insert(x), Ok(()));
+        }
+        let error = queue.try_insert(3).unwrap_err();
+        assert_eq!(
+            error.reason(),
+            &ReserveError::Capacity(CapacityError { limit: 4 })
+        );
+        assert_eq!(error.into_element(), 3);
+        assert_eq!(queue.take_front(), Some(8));
+        assert_eq!(queue.try_insert(3), Ok(()));
+        let order: Vec<_> = std::iter::from_fn(|| queue.take_front()).collect();
+        assert_eq!(order, [7, 3, 2, 1]);
+    }
+
+    #[test]
+    fn slice_storage_queue_operations() {
+        let mut buf = [const { MaybeUninit::uninit() }; 8];
+        let mut queue =
+            GenericPriorityQueue::<_, _, _>::with_storage(SliceStorage::new(&mut buf), MinOrder);
+        queue.extend(0..6);
+        queue.insert(-1);
+        queue.retain_mut(|x| {
+            *x = -*x;
+            *x != 0
+        });
+        assert_eq!(queue.remove_first_matching(|&x| x == -3), Some(-3));
+        let order: Vec<_> = queue.into_iter_sorted().collect();
+        assert_eq!(order, [-5, -4, -2, -1, 1]);
+    }
+
+    #[test]
+    fn slice_storage_drops_remaining_elements() {
+        let token = Rc::new(());
+        let mut buf = [const { MaybeUninit::uninit() }; 8];
+        let mut storage = SliceStorage::new(&mut buf);
+        for _ in 0..5 {
+            storage.push(token.clone());
+        }
+        assert_eq!(storage.capacity(), 8);
+        drop(storage);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn vec_storage_keeps_existing_elements() {
+        let queue = GenericPriorityQueue::<_, _, _, 3>::with_storage(vec![5, 3, 9, 1, 4], MaxOrder);
+        assert_eq!(queue.peek(), Some(&9));
+        assert_eq!(queue.check_heap_invariant(), Ok(()));
+        let mut storage = queue.into_storage();
+        storage.sort();
+        assert_eq!(storage, [1, 3, 4, 5, 9]);
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use std::vec::Vec;
+
+use crate::{
+    ClosedError, Comparator, MinOrder, PopTimeoutError, PriorityQueue, PushError, TryPopError,
+};
+
+/// A priority queue that can be shared between threads, where [`pop`] waits for an element.
+///
+/// The queue is guarded by a mutex, so each operation takes a lock. Once the queue is [closed],
+/// pushing fails, and popping returns the remaining elements and then an error instead of
+/// waiting.
+///
+/// A panicking comparator poisons the mutex, but the other threads ignore the poison and keep
+/// using the queue, since the inner queue repairs itself as described on
+/// [`GenericPriorityQueue`].
+///
+/// [`pop`]: SyncPriorityQueue::pop
+/// [closed]: SyncPriorityQueue::close
+/// [`GenericPriorityQueue`]: crate::GenericPriorityQueue
+pub struct SyncPriorityQueue<T, F = MinOrder> {
+    state: Mutex<State<T, F>>,
+    /// Notified when an element is pushed or the queue is closed.
+    changed: Condvar,
+}
+
+struct State<T, F> {
+    queue: PriorityQueue<T, F>,
+    closed: bool,
+}
+
+impl<T: PartialOrd> SyncPriorityQueue<T> {
+    pub fn new(data: Vec<T>) -> Self {
+        Self::with_comparator(data, MinOrder)
+    }
+}
+
+impl<T: PartialOrd> Default for SyncPriorityQueue<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> bool> SyncPriorityQueue<T, F> {
+    pub fn with_ordering(data: Vec<T>, ordering: F) -> Self {
+        Self::with_comparator(data, ordering)
+    }
+}
+
+impl<T, F: Comparator<T>> From<PriorityQueue<T, F>> for SyncPriorityQueue<T, F> {
+    fn from(queue: PriorityQueue<T, F>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                queue,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+impl<T, F: Comparator<T>> SyncPriorityQueue<T, F> {
+    pub fn with_comparator(data: Vec<T>, cmp: F) -> Self {
+        PriorityQueue::with_comparator(data, cmp).into()
+    }
+
+    /// Inserts `element` and wakes one waiting [`pop`], or gives the element back if the queue is
+    /// closed or full.
+    ///
+    /// The queue is only ever full if it was made from a [`PriorityQueue`] with a [limit].
+    ///
+    /// [`pop`]: SyncPriorityQueue::pop
+    /// [limit]: crate::GenericPriorityQueue::set_limit
+    pub fn push(&self, element: T) -> Result<(), PushError<T>> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(PushError::Closed(element));
+        }
+        state.queue.try_insert(element).map_err(PushError::Full)?;
+        drop(state);
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    /// Takes the front element without waiting.
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        let mut state = self.lock();
+        match state.queue.take_front() {
+            Some(element) => Ok(element),
+            None if state.closed => Err(TryPopError::Closed),
+            None => Err(TryPopError::Empty),
+        }
+    }
+
+    /// Takes the front element, waiting for one to be pushed if the queue is empty.
+    ///
+    /// Returns an error once the queue is closed and empty.
+    pub fn pop(&self) -> Result<T, ClosedError> {
+        let state = self.lock();
+        let mut state = self
+            .changed
+            .wait_while(state, |state| state.queue.is_empty() && !state.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        state.queue.take_front().ok_or(ClosedError)
+    }
+
+    /// Like [`pop`](SyncPriorityQueue::pop), but gives up after waiting for `timeout`.
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopTimeoutError> {
+        let state = self.lock();
+        let (mut state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| {
+                state.queue.is_empty() && !state.closed
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        match state.queue.take_front() {
+            Some(element) => Ok(element),
+            None if state.closed => Err(PopTimeoutError::Closed),
+            None => Err(PopTimeoutError::Timeout),
+        }
+    }
+
+    /// Closes the queue and wakes every thread waiting in [`pop`](SyncPriorityQueue::pop).
+    ///
+    /// The elements already in the queue can still be popped.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Returns the number of elements. Other threads may change it as soon as this returns.
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    /// Returns `true` if the queue is empty. Other threads may change it as soon as this returns.
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    /// Consumes the queue and returns the inner queue.
+    pub fn into_inner(self) -> PriorityQueue<T, F> {
+        self.state
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .queue
+    }
+
+    /// Locks the state. The inner queue repairs itself after a panic in the comparator, so a
+    /// poisoned mutex is safe to use.
+    fn lock(&self) -> MutexGuard<'_, State<T, F>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::{ClosedError, PopTimeoutError, PushError, SyncPriorityQueue, TryPopError};
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncPriorityQueue<String>>();
+    }
+
+    #[test]
+    fn single_thread() {
+        let queue = SyncPriorityQueue::with_ordering(vec![3, 1, 2], |a, b| a > b);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.try_pop(), Ok(3));
+        assert_eq!(queue.pop(), Ok(2));
+        assert_eq!(queue.push(5), Ok(()));
+        assert_eq!(queue.pop_timeout(Duration::ZERO), Ok(5));
+        assert_eq!(queue.pop(), Ok(1));
+        assert_eq!(queue.try_pop(), Err(TryPopError::Empty));
+        assert_eq!(
+            queue.pop_timeout(Duration::from_millis(10)),
+            Err(PopTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn close_drains_then_fails() {
+        let queue = SyncPriorityQueue::new(vec![2, 1]);
+        queue.close();
+        assert!(queue.is_closed());
+        assert_eq!(queue.push(0), Err(PushError::Closed(0)));
+        assert_eq!(queue.pop(), Ok(1));
+        assert_eq!(queue.try_pop(), Ok(2));
+        assert_eq!(queue.pop(), Err(ClosedError));
+        assert_eq!(queue.try_pop(), Err(TryPopError::Closed));
+        assert_eq!(
+            queue.pop_timeout(Duration::ZERO),
+            Err(PopTimeoutError::Closed)
+        );
+    }
+
+    #[test]
+    fn push_to_limited_queue() {
+        use crate::{CapacityError, PriorityQueue, ReserveError};
+
+        let mut inner = PriorityQueue::new(vec![3, 1]);
+        inner.set_limit(Some(2));
+        let queue = SyncPriorityQueue::from(inner);
+        let error = queue.push(2).unwrap_err();
+        assert!(matches!(
+            &error,
+            PushError::Full(error)
+                if error.reason() == &ReserveError::Capacity(CapacityError { limit: 2 })
+        ));
+        assert_eq!(error.into_element(), 2);
+        assert_eq!(queue.pop(), Ok(1));
+        assert_eq!(queue.push(2), Ok(()));
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn close_wakes_waiters() {
+        let queue = Arc::new(SyncPriorityQueue::<u32>::default());
+        let waiters: Vec<_> = (0..4)
+            .map(|i| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    if i % 2 == 0 {
+                        queue.pop().map_err(|_| ())
+                    } else {
+                        queue.pop_timeout(Duration::from_secs(60)).map_err(|_| ())
+                    }
+                })
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        queue.close();
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), Err(()));
+        }
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn pop_waits_for_push() {
+        let queue = Arc::new(SyncPriorityQueue::<u32>::default());
+        let consumer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || queue.pop())
+        };
+        thread::sleep(Duration::from_millis(20));
+        queue.push(7).unwrap();
+        assert_eq!(consumer.join().unwrap(), Ok(7));
+    }
+
+    #[test]
+    fn many_producers_and_consumers() {
+        const PRODUCERS: u64 = 8;
+        const CONSUMERS: usize = 8;
+        const PER_PRODUCER: u64 = 5_000;
+
+        let queue = Arc::new(SyncPriorityQueue::<u64>::default());
+        let start = Arc::new(Barrier::new(PRODUCERS as usize + CONSUMERS));
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|i| {
+                let queue = Arc::clone(&queue);
+                let start = Arc::clone(&start);
+                thread::spawn(move || {
+                    start.wait();
+                    let mut taken = Vec::new();
+                    loop {
+                        let next = if i % 2 == 0 {
+                            queue.pop().map_err(|_| ())
+                        } else {
+                            match queue.pop_timeout(Duration::from_millis(1)) {
+                                Err(PopTimeoutError::Timeout) => continue,
+                                result => result.map_err(|_| ()),
+                            }
+                        };
+                        match next {
+                            Ok(x) => taken.push(x),
+                            Err(()) => return taken,
+                        }
+                    }
+                })
+            })
+            .collect();
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = Arc::clone(&queue);
+                let start = Arc::clone(&start);
+                thread::spawn(move || {
+                    start.wait();
+                    for i in 0..PER_PRODUCER {
+                        queue.push(p * PER_PRODUCER + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        queue.close();
+        let mut seen = HashSet::new();
+        for consumer in consumers {
+            for x in consumer.join().unwrap() {
+                assert!(seen.insert(x), "{x} was popped twice");
+            }
+        }
+        assert_eq!(seen.len() as u64, PRODUCERS * PER_PRODUCER);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn each_consumer_sees_increasing_elements_when_prefilled() {
+        let queue = Arc::new(SyncPriorityQueue::new((0..20_000).rev().collect()));
+        queue.close();
+        let consumers: Vec<_> = (0..8)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let mut taken = Vec::new();
+                    while let Ok(x) = queue.try_pop() {
+                        taken.push(x);
+                    }
+                    taken
+                })
+            })
+            .collect();
+        let mut total = 0;
+        for consumer in consumers {
+            let taken = consumer.join().unwrap();
+            assert!(taken.windows(2).all(|w| w[0] < w[1]));
+            total += taken.len();
+        }
+        assert_eq!(total, 20_000);
+    }
+}
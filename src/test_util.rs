@@ -0,0 +1,12 @@
+//! Helpers shared by the unit tests.
+
+/// Returns a xorshift32 generator starting from `seed`, so the tests do not need a `rand`
+/// dependency.
+pub(crate) fn xorshift(mut seed: u32) -> impl FnMut() -> u32 {
+    move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    }
+}